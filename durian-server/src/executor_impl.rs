@@ -4,7 +4,12 @@ use crate::provider_adaptor::ProviderAdaptor;
 use capnp::capability::Promise;
 use capnp::Error;
 use durian::address::Address;
+use durian::execute::{ExecuteOptions, ExecutionOutcome};
+use durian::record::Recorder;
 use primitive_types::{H256, U256};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
 use tokio::sync::oneshot;
 use tokio::sync::oneshot::error::TryRecvError;
 
@@ -40,14 +45,57 @@ impl<'a> From<durian_capnp::transaction::Reader<'a>>
     }
 }
 
-pub struct ExecutorImpl {}
+impl<'a> From<durian_capnp::block_env::Reader<'a>> for Result<durian::block::BlockEnv, Error> {
+    fn from(reader: durian_capnp::block_env::Reader<'a>) -> Self {
+        Ok(durian::block::BlockEnv {
+            number: reader.get_number(),
+            timestamp: reader.get_timestamp(),
+            author: Address::from_slice(reader.get_author()?),
+            difficulty: U256::from_little_endian(reader.get_difficulty()?),
+            gas_limit: U256::from_little_endian(reader.get_gas_limit()?),
+            base_fee: U256::from_little_endian(reader.get_base_fee()?),
+            chain_id: U256::from_little_endian(reader.get_chain_id()?),
+        })
+    }
+}
+
+pub struct ExecutorImpl {
+    record_dir: Option<PathBuf>,
+    counter: usize,
+    options: ExecuteOptions,
+}
 
 impl ExecutorImpl {
-    pub fn new() -> Self {
-        ExecutorImpl {}
+    pub fn new(record_dir: Option<PathBuf>, dev_mode: bool) -> Self {
+        ExecutorImpl {
+            record_dir,
+            counter: 0,
+            options: ExecuteOptions {
+                dev_mode,
+                ..Default::default()
+            },
+        }
     }
 }
 
+fn execute_recorded(
+    adaptor: &mut ProviderAdaptor,
+    env: &durian::block::BlockEnv,
+    transaction: &durian::transaction::Transaction,
+    options: &ExecuteOptions,
+    path: PathBuf,
+) -> Result<ExecutionOutcome, durian::error::Error> {
+    let file = File::create(&path).map_err(|e| durian::error::Error::Other {
+        msg: format!("Unable to create {:?}: {}", path, e),
+    })?;
+    let mut recorder = Recorder::new(adaptor, BufWriter::new(file), env, transaction)?;
+    let result = durian::execute::execute_with_options(&mut recorder, env, transaction, options);
+    recorder.finish()?;
+    info!("Recorded provider calls to {:?}", path);
+
+    result
+}
+
 unsafe impl Send for durian_capnp::provider::Client {}
 //unsafe impl Sync for durian_capnp::provider::Client {}
 
@@ -58,14 +106,31 @@ impl executor::Server for ExecutorImpl {
         mut results: executor::ExecuteResults,
     ) -> Promise<(), Error> {
         let provider_client = pry!(pry!(params.get()).get_provider());
+        let env = pry!(pry!(pry!(params.get()).get_block_env()).into());
         let transaction = pry!(pry!(pry!(params.get()).get_transaction()).into());
         let (tx, mut rx) = oneshot::channel();
+        let record_path = match &self.record_dir {
+            Some(dir) => {
+                self.counter += 1;
+                Some(dir.join(format!("execution-{}.jsonl", self.counter)))
+            }
+            None => None,
+        };
+        let options = self.options.clone();
 
         tokio::task::spawn(async move {
             debug!("provider: {:?}", std::thread::current().id());
             let mut adaptor = ProviderAdaptor::new(provider_client);
 
-            let result = durian::execute::execute(&mut adaptor, &transaction).unwrap();
+            let result = match record_path {
+                Some(path) => execute_recorded(&mut adaptor, &env, &transaction, &options, path),
+                None => durian::execute::execute_with_options(
+                    &mut adaptor,
+                    &env,
+                    &transaction,
+                    &options,
+                ),
+            };
 
             tx.send(result).unwrap();
         });
@@ -79,7 +144,10 @@ impl executor::Server for ExecutorImpl {
                     Err(e) => {
                         return Err(Error::failed(format!("{}", e)));
                     }
-                    Ok(result_data) => {
+                    Ok(Err(e)) => {
+                        return Err(Error::failed(format!("{}", e)));
+                    }
+                    Ok(Ok(outcome)) => {
                         tokio::time::delay_for(std::time::Duration::from_millis(10 as u64)).await;
 
                         let mut tmp = Vec::new();
@@ -87,13 +155,72 @@ impl executor::Server for ExecutorImpl {
 
                         let mut builder = results.get().get_result_data().unwrap();
 
-                        result_data.gas_left.to_little_endian(&mut tmp);
-                        builder.set_gas_left(&tmp);
-                        builder.set_data(&result_data.data);
-                        builder.set_contract(&result_data.contract.as_bytes());
-
-                        // TODO: Implement it later
-                        //builder.set_logs();
+                        outcome.gas_used().to_little_endian(&mut tmp);
+                        builder.set_gas_used(&tmp);
+
+                        let debug_messages = outcome.debug_messages();
+                        let mut list = builder
+                            .reborrow()
+                            .init_debug_messages(debug_messages.len() as u32);
+                        for (i, debug_message) in debug_messages.iter().enumerate() {
+                            let mut entry = list.reborrow().get(i as u32);
+                            debug_message.gas_used.to_little_endian(&mut tmp);
+                            entry.set_gas_used(&tmp);
+                            entry.set_message(&debug_message.message);
+                        }
+
+                        match outcome {
+                            ExecutionOutcome::Success { result, .. } => {
+                                builder.set_status(durian_capnp::Status::Success);
+                                result.gas_left.to_little_endian(&mut tmp);
+                                builder.set_gas_left(&tmp);
+                                builder.set_data(&result.data);
+                                builder.set_contract(&result.contract.as_bytes());
+
+                                let mut logs = builder.reborrow().init_logs(result.logs.len() as u32);
+                                for (i, log) in result.logs.iter().enumerate() {
+                                    let mut entry = logs.reborrow().get(i as u32);
+                                    entry.set_address(log.address.as_bytes());
+                                    let mut topics =
+                                        entry.reborrow().init_topics(log.topics.len() as u32);
+                                    for (j, topic) in log.topics.iter().enumerate() {
+                                        topics.set(j as u32, topic.as_bytes());
+                                    }
+                                    let mut data = entry.init_data(log.data.len() as u32);
+                                    for (j, byte) in log.data.iter().enumerate() {
+                                        data.set(j as u32, *byte as i8);
+                                    }
+                                }
+                            }
+                            ExecutionOutcome::Revert {
+                                data, diagnostics, ..
+                            } => {
+                                builder.set_status(durian_capnp::Status::Revert);
+                                builder.set_data(&data);
+                                builder.set_message(&diagnostics.message);
+                            }
+                            ExecutionOutcome::OutOfGas { diagnostics, .. } => {
+                                builder.set_status(durian_capnp::Status::OutOfGas);
+                                builder.set_message(&diagnostics.message);
+                            }
+                            ExecutionOutcome::Trap { diagnostics, .. } => {
+                                builder.set_status(durian_capnp::Status::Trap);
+                                builder.set_message(&diagnostics.message);
+                            }
+                            ExecutionOutcome::ContractPanic {
+                                raw_payload,
+                                diagnostics,
+                                ..
+                            } => {
+                                builder.set_status(durian_capnp::Status::ContractPanic);
+                                builder.set_message(&diagnostics.message);
+                                builder.set_panic_payload(&raw_payload);
+                            }
+                            ExecutionOutcome::HostFailure { diagnostics, .. } => {
+                                builder.set_status(durian_capnp::Status::HostFailure);
+                                builder.set_message(&diagnostics.message);
+                            }
+                        }
 
                         break;
                     }
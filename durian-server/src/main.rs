@@ -15,6 +15,7 @@ use durian_capnp::executor;
 use executor_impl::ExecutorImpl;
 use futures::{AsyncReadExt, FutureExt, TryFutureExt};
 use std::net::ToSocketAddrs;
+use std::path::PathBuf;
 use tokio::net::TcpListener;
 use log::Level;
 
@@ -23,11 +24,32 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     simple_logger::init_with_level(Level::Debug).unwrap();
 
     let args: Vec<String> = ::std::env::args().collect();
-    if args.len() != 2 {
-        println!("usage: {} HOST:PORT", args[0]);
+    let usage = format!("usage: {} HOST:PORT [--record DIR] [--dev]", args[0]);
+    if args.len() < 2 {
+        println!("{}", usage);
         return Ok(());
     }
 
+    let mut record_dir = None;
+    let mut dev_mode = false;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--dev" => dev_mode = true,
+            "--record" => match options.next() {
+                Some(dir) => record_dir = Some(PathBuf::from(dir)),
+                None => {
+                    println!("{}", usage);
+                    return Ok(());
+                }
+            },
+            _ => {
+                println!("{}", usage);
+                return Ok(());
+            }
+        }
+    }
+
     let addr = args[1]
         .to_socket_addrs()
         .unwrap()
@@ -37,7 +59,7 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tokio::task::LocalSet::new()
         .run_until(async move {
             let mut listener = TcpListener::bind(&addr).await?;
-            let executor_impl = ExecutorImpl::new();
+            let executor_impl = ExecutorImpl::new(record_dir, dev_mode);
             let executor: executor::Client = capnp_rpc::new_client(executor_impl);
 
             loop {
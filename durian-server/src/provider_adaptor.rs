@@ -159,27 +159,62 @@ impl Provider for ProviderAdaptor {
         futures::executor::block_on(handle).map_err(|e: Error| e.into())
     }
 
-    fn block_hash(&self, _num: u64) -> Result<H256, durian::error::Error> {
-        Ok(H256::zero())
-    }
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), durian::error::Error> {
+        let mut request = self.client.remove_storage_request();
+        {
+            request.get().set_address(address.as_bytes());
+            request.get().set_key(key.as_bytes());
+        }
+        let handle = async move {
+            debug!("Try ot call `remove_storage` method in client");
+            request.send().promise.await?;
 
-    fn timestamp(&self) -> u64 {
-        0
-    }
+            Ok(())
+        };
 
-    fn block_number(&self) -> u64 {
-        0
+        futures::executor::block_on(handle).map_err(|e: Error| e.into())
     }
 
-    fn block_author(&self) -> Result<Address, durian::error::Error> {
-        Err(durian::error::Error::NotSupported)
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, durian::error::Error> {
+        let mut request = self.client.storage_bytes_at_request();
+        {
+            request.get().set_address(address.as_bytes());
+            request.get().set_key(key.as_bytes());
+        }
+        let handle = async move {
+            debug!("Try ot call `storage_bytes_at` method in client");
+            let result = request.send().promise.await?;
+            let value = result.get()?.get_value()?;
+
+            Ok(value.to_vec())
+        };
+
+        futures::executor::block_on(handle).map_err(|e: Error| e.into())
     }
 
-    fn difficulty(&self) -> Result<U256, durian::error::Error> {
-        Err(durian::error::Error::NotSupported)
+    fn set_storage_bytes(
+        &mut self,
+        address: &Address,
+        key: &H256,
+        value: &[u8],
+    ) -> Result<(), durian::error::Error> {
+        let mut request = self.client.set_storage_bytes_request();
+        {
+            request.get().set_address(address.as_bytes());
+            request.get().set_key(key.as_bytes());
+            request.get().set_value(value);
+        }
+        let handle = async move {
+            debug!("Try ot call `set_storage_bytes` method in client");
+            request.send().promise.await?;
+
+            Ok(())
+        };
+
+        futures::executor::block_on(handle).map_err(|e: Error| e.into())
     }
 
-    fn gas_limit(&self) -> Result<U256, durian::error::Error> {
-        Ok(U256::zero())
+    fn block_hash(&self, _num: u64) -> Result<H256, durian::error::Error> {
+        Ok(H256::zero())
     }
 }
@@ -0,0 +1,167 @@
+use crate::bytes::Bytes;
+use durian::address::Address;
+use durian::block::BlockEnv;
+use durian::transaction::Transaction;
+use primitive_types::{H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// One state test: a transaction executed on top of `pre`, and what it must lead to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateTest {
+    pub env: Env,
+    pub pre: BTreeMap<Address, PreAccount>,
+    pub transaction: TestTransaction,
+    pub expect: Expectation,
+}
+
+/// Block the transaction is executed in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Env {
+    #[serde(flatten)]
+    pub block: BlockEnv,
+    #[serde(default)]
+    pub block_hashes: BTreeMap<u64, H256>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreAccount {
+    #[serde(default)]
+    pub nonce: U256,
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default)]
+    pub code: Bytes,
+    /// Path of a wasm file to use as `code`, relative to the fixture.
+    #[serde(default)]
+    pub code_file: Option<String>,
+    #[serde(default)]
+    pub storage: BTreeMap<H256, H256>,
+    #[serde(default)]
+    pub storage_bytes: BTreeMap<H256, Bytes>,
+}
+
+/// Transaction of the test. Exactly one of `call` and `create` must be set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestTransaction {
+    pub sender: Address,
+    #[serde(default)]
+    pub value: U256,
+    pub gas: U256,
+    #[serde(default)]
+    pub gas_price: U256,
+    #[serde(default)]
+    pub call: Option<Address>,
+    #[serde(default)]
+    pub create: Option<TestCreate>,
+    #[serde(default)]
+    pub args: Bytes,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestCreate {
+    #[serde(default)]
+    pub code: Bytes,
+    /// Path of a wasm file to use as `code`, relative to the fixture.
+    #[serde(default)]
+    pub code_file: Option<String>,
+    #[serde(default)]
+    pub salt: H256,
+}
+
+/// What the execution must lead to. Only the fields which are set are checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Expectation {
+    #[serde(default)]
+    pub status: Option<Status>,
+    #[serde(default)]
+    pub gas_used: Option<U256>,
+    /// Data returned by a successful execution, or passed to `revert`.
+    #[serde(default)]
+    pub output: Option<Bytes>,
+    #[serde(default)]
+    pub logs: Option<Vec<TestLog>>,
+    #[serde(default)]
+    pub post: BTreeMap<Address, PostAccount>,
+}
+
+/// How the execution ended, `error` meaning `execute` itself failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Success,
+    Revert,
+    OutOfGas,
+    Trap,
+    ContractPanic,
+    HostFailure,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostAccount {
+    #[serde(default)]
+    pub exists: Option<bool>,
+    #[serde(default)]
+    pub nonce: Option<U256>,
+    #[serde(default)]
+    pub balance: Option<U256>,
+    /// Expected storage slots, `null` meaning the slot must not be set.
+    #[serde(default)]
+    pub storage: BTreeMap<H256, Option<H256>>,
+    #[serde(default)]
+    pub storage_bytes: BTreeMap<H256, Bytes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestLog {
+    pub address: Address,
+    #[serde(default)]
+    pub topics: Vec<H256>,
+    #[serde(default)]
+    pub data: Bytes,
+}
+
+/// Reads the tests of a fixture file, which maps test names to tests.
+pub fn load(path: &Path) -> Result<BTreeMap<String, StateTest>, String> {
+    let json = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    serde_json::from_str(&json).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// `code`, or the content of `code_file` if it is set.
+pub fn resolve_code(code: &Bytes, code_file: &Option<String>, dir: &Path) -> Result<Vec<u8>, String> {
+    match code_file {
+        Some(file) => {
+            let path = dir.join(file);
+            fs::read(&path).map_err(|err| format!("{}: {}", path.display(), err))
+        }
+        None => Ok(code.0.clone()),
+    }
+}
+
+impl TestTransaction {
+    pub fn to_transaction(&self, dir: &Path) -> Result<Transaction, String> {
+        match (&self.call, &self.create) {
+            (Some(address), None) => Ok(Transaction::make_call(
+                self.sender,
+                *address,
+                self.value,
+                self.gas,
+                self.gas_price,
+                self.args.0.clone(),
+            )),
+            (None, Some(create)) => Ok(Transaction::make_create(
+                self.sender,
+                self.value,
+                self.gas,
+                self.gas_price,
+                resolve_code(&create.code, &create.code_file, dir)?,
+                self.args.0.clone(),
+                create.salt,
+            )),
+            _ => Err("transaction must set exactly one of `call` and `create`".to_string()),
+        }
+    }
+}
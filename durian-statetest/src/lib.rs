@@ -0,0 +1,4 @@
+pub mod bytes;
+pub mod fixture;
+pub mod provider;
+pub mod runner;
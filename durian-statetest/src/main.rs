@@ -0,0 +1,72 @@
+use durian_statetest::runner;
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Fixture files under `path`, which is either a fixture or a directory of them.
+fn collect(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|err| format!("{}: {}", path.display(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension() == Some(OsStr::new("json")) {
+            collect(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: durian-statetest <fixture or directory>...");
+        process::exit(2);
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if let Err(err) = collect(Path::new(path), &mut files) {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+    for file in &files {
+        let results = match runner::run_file(file) {
+            Ok(results) => results,
+            Err(err) => {
+                println!("FAIL {}: {}", file.display(), err);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for result in results {
+            if result.passed() {
+                println!("PASS {}::{}", file.display(), result.name);
+                passed += 1;
+            } else {
+                println!("FAIL {}::{}", file.display(), result.name);
+                for failure in &result.failures {
+                    println!("    {}", failure);
+                }
+                failed += 1;
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
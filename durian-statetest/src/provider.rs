@@ -0,0 +1,90 @@
+use crate::fixture::Env;
+use durian::address::Address;
+use durian::dump::{DumpAccount, StateDump};
+use durian::error::Error;
+use durian::provider::{Provider, StateAccount};
+use primitive_types::{H256, U256};
+
+/// Provider which keeps the whole state in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    pub state: StateDump,
+    pub env: Env,
+}
+
+impl MemoryProvider {
+    pub fn new(state: StateDump, env: Env) -> Self {
+        MemoryProvider { state, env }
+    }
+
+    fn account_mut(&mut self, address: &Address) -> &mut DumpAccount {
+        self.state.accounts.entry(*address).or_default()
+    }
+}
+
+impl Provider for MemoryProvider {
+    fn exist(&self, address: &Address) -> bool {
+        self.state.accounts.contains_key(address)
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        match self.state.accounts.get(address) {
+            Some(acc) => Ok(StateAccount {
+                nonce: acc.nonce,
+                balance: acc.balance,
+                code: acc.code.clone(),
+            }),
+            None => Err(Error::InvalidAddress { address: *address }),
+        }
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        let acc = self.account_mut(address);
+        acc.balance = *bal;
+        acc.nonce = *nonce;
+        Ok(())
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        self.account_mut(address).code = code.clone();
+        Ok(())
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        match self.state.accounts.get(address).and_then(|acc| acc.storage.get(key)) {
+            Some(value) => Ok(*value),
+            None => Err(Error::InvalidStorageKey { key: *key }),
+        }
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        self.account_mut(address).storage.insert(*key, *value);
+        Ok(())
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        self.account_mut(address).storage.remove(key);
+        Ok(())
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        match self.state.accounts.get(address).and_then(|acc| acc.storage_bytes.get(key)) {
+            Some(value) => Ok(value.clone()),
+            None => Err(Error::InvalidStorageKey { key: *key }),
+        }
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        self.account_mut(address).storage_bytes.insert(*key, value.to_vec());
+        Ok(())
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        match self.env.block_hashes.get(&block_no) {
+            Some(hash) => Ok(*hash),
+            None => Err(Error::Other {
+                msg: format!("Unknown block {}", block_no),
+            }),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::bytes::Bytes;
+use crate::fixture::{self, Expectation, StateTest, Status, TestLog};
+use crate::provider::MemoryProvider;
+use durian::dump::{DumpAccount, StateDump};
+use durian::execute::{self, ExecutionOutcome};
+use std::fmt::Debug;
+use std::path::Path;
+
+/// Outcome of one test. It passed if there are no failures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    pub name: String,
+    pub failures: Vec<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Runs all the tests of the fixture at `path`.
+pub fn run_file(path: &Path) -> Result<Vec<TestResult>, String> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let tests = fixture::load(path)?;
+    Ok(tests
+        .iter()
+        .map(|(name, test)| TestResult {
+            name: name.clone(),
+            failures: match run_test(test, dir) {
+                Ok(failures) => failures,
+                Err(err) => vec![err],
+            },
+        })
+        .collect())
+}
+
+/// Runs `test` and returns how the result differs from what it expects.
+///
+/// `dir` is the directory `code_file` paths are relative to.
+pub fn run_test(test: &StateTest, dir: &Path) -> Result<Vec<String>, String> {
+    let mut pre = StateDump::default();
+    for (addr, acc) in &test.pre {
+        let account = DumpAccount {
+            nonce: acc.nonce,
+            balance: acc.balance,
+            code: fixture::resolve_code(&acc.code, &acc.code_file, dir)?,
+            storage: acc.storage.clone(),
+            storage_bytes: acc
+                .storage_bytes
+                .iter()
+                .map(|(key, value)| (*key, value.0.clone()))
+                .collect(),
+        };
+        pre.accounts.insert(*addr, account);
+    }
+
+    let transaction = test.transaction.to_transaction(dir)?;
+    let mut provider = MemoryProvider::new(pre, test.env.clone());
+    let result = execute::execute(&mut provider, &test.env.block, &transaction);
+
+    let mut failures = Vec::new();
+    check_outcome(&test.expect, &result, &mut failures);
+    check_post(&test.expect, &provider.state, &mut failures);
+    Ok(failures)
+}
+
+fn check_outcome(
+    expect: &Expectation,
+    result: &Result<ExecutionOutcome, durian::error::Error>,
+    failures: &mut Vec<String>,
+) {
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            if expect.status != Some(Status::Error) {
+                failures.push(format!("execute failed: {}", err));
+            }
+            return;
+        }
+    };
+
+    let (status, output, logs) = match outcome {
+        ExecutionOutcome::Success { result, .. } => (Status::Success, result.data.clone(), result.logs.clone()),
+        ExecutionOutcome::Revert { data, .. } => (Status::Revert, data.clone(), vec![]),
+        ExecutionOutcome::OutOfGas { .. } => (Status::OutOfGas, vec![], vec![]),
+        ExecutionOutcome::Trap { .. } => (Status::Trap, vec![], vec![]),
+        ExecutionOutcome::ContractPanic { .. } => (Status::ContractPanic, vec![], vec![]),
+        ExecutionOutcome::HostFailure { .. } => (Status::HostFailure, vec![], vec![]),
+    };
+
+    compare("status", &expect.status, &status, failures);
+    compare("gas used", &expect.gas_used, &outcome.gas_used(), failures);
+    compare("output", &expect.output, &Bytes(output), failures);
+    let logs: Vec<TestLog> = logs
+        .into_iter()
+        .map(|log| TestLog {
+            address: log.address,
+            topics: log.topics,
+            data: Bytes(log.data),
+        })
+        .collect();
+    if let Some(expected) = &expect.logs {
+        compare("log count", &Some(expected.len()), &logs.len(), failures);
+        for (index, (expected, actual)) in expected.iter().zip(logs.iter()).enumerate() {
+            compare(&format!("log {}", index), &Some(expected), &actual, failures);
+        }
+    }
+}
+
+fn check_post(expect: &Expectation, state: &StateDump, failures: &mut Vec<String>) {
+    for (addr, post) in &expect.post {
+        let acc = state.accounts.get(addr);
+        compare(&format!("existence of {:?}", addr), &post.exists, &acc.is_some(), failures);
+        let acc = match acc {
+            Some(acc) => acc,
+            None => {
+                if post.exists.is_none() {
+                    failures.push(format!("account {:?} is missing", addr));
+                }
+                continue;
+            }
+        };
+
+        compare(&format!("nonce of {:?}", addr), &post.nonce, &acc.nonce, failures);
+        compare(&format!("balance of {:?}", addr), &post.balance, &acc.balance, failures);
+        for (key, value) in &post.storage {
+            let actual = acc.storage.get(key).cloned();
+            compare(&format!("storage {:?} of {:?}", key, addr), &Some(*value), &actual, failures);
+        }
+        for (key, value) in &post.storage_bytes {
+            let actual = Bytes(acc.storage_bytes.get(key).cloned().unwrap_or_default());
+            compare(&format!("storage bytes {:?} of {:?}", key, addr), &Some(value.clone()), &actual, failures);
+        }
+    }
+}
+
+/// Records a failure if `expected` is set and differs from `actual`.
+fn compare<T: PartialEq + Debug>(what: &str, expected: &Option<T>, actual: &T, failures: &mut Vec<String>) {
+    if let Some(expected) = expected {
+        if expected != actual {
+            failures.push(format!("{}: expected {:?}, got {:?}", what, expected, actual));
+        }
+    }
+}
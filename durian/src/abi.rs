@@ -0,0 +1,928 @@
+//! Solidity ABI: contract interfaces parsed from ABI JSON, and the encoding of
+//! call data, return data and event logs.
+//!
+//! Integers are held as `U256`. Signed integers use two's complement, so an
+//! `int8` of `-1` is `U256::MAX`.
+
+use address::Address;
+use error::Error;
+use log_entry::LogEntry;
+use primitive_types::{H256, U256};
+use serde_json;
+use std::fmt;
+use std::slice;
+use utils;
+
+/// Type of a parameter as declared in the ABI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    /// Parses a canonical type name like `uint256` or `address[2][]`.
+    ///
+    /// `components` are the member types of a `tuple`, and ignored otherwise.
+    pub fn parse(name: &str, components: &[ParamType]) -> Result<ParamType, Error> {
+        if name.ends_with(']') {
+            let open = name.rfind('[').ok_or_else(|| bad_type(name))?;
+            let inner = ParamType::parse(&name[..open], components)?;
+            let len = &name[open + 1..name.len() - 1];
+            if len.is_empty() {
+                return Ok(ParamType::Array(Box::new(inner)));
+            }
+            let len = len.parse().map_err(|_| bad_type(name))?;
+            return Ok(ParamType::FixedArray(Box::new(inner), len));
+        }
+
+        let sized = |prefix: &str, default: usize| -> Option<Result<usize, Error>> {
+            if !name.starts_with(prefix) {
+                return None;
+            }
+            let size = &name[prefix.len()..];
+            if size.is_empty() {
+                return Some(Ok(default));
+            }
+            Some(size.parse().map_err(|_| bad_type(name)))
+        };
+
+        let kind = match name {
+            "address" => ParamType::Address,
+            "bool" => ParamType::Bool,
+            "bytes" => ParamType::Bytes,
+            "string" => ParamType::String,
+            "tuple" => ParamType::Tuple(components.to_vec()),
+            _ => {
+                if let Some(size) = sized("uint", 256) {
+                    ParamType::Uint(size?)
+                } else if let Some(size) = sized("int", 256) {
+                    ParamType::Int(size?)
+                } else if let Some(size) = sized("bytes", 32) {
+                    ParamType::FixedBytes(size?)
+                } else {
+                    return Err(bad_type(name));
+                }
+            }
+        };
+
+        match kind {
+            ParamType::Uint(size) | ParamType::Int(size) if size == 0 || size > 256 || size % 8 != 0 => {
+                Err(bad_type(name))
+            }
+            ParamType::FixedBytes(size) if size == 0 || size > 32 => Err(bad_type(name)),
+            kind => Ok(kind),
+        }
+    }
+
+    /// Whether values of the type are a single word, which is what an indexed
+    /// event parameter stores in its topic as is.
+    pub fn is_value_type(&self) -> bool {
+        matches!(
+            self,
+            ParamType::Uint(_)
+                | ParamType::Int(_)
+                | ParamType::Address
+                | ParamType::Bool
+                | ParamType::FixedBytes(_)
+        )
+    }
+
+    /// Whether the encoding of the type is stored in the tail.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(inner, _) => inner.is_dynamic(),
+            ParamType::Tuple(members) => members.iter().any(ParamType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Size of the head of the type in an encoded tuple.
+    fn head_len(&self) -> usize {
+        if self.is_dynamic() {
+            return 32;
+        }
+        match self {
+            ParamType::FixedArray(inner, len) => inner.head_len() * len,
+            ParamType::Tuple(members) => members.iter().map(ParamType::head_len).sum(),
+            _ => 32,
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamType::Uint(size) => write!(f, "uint{}", size),
+            ParamType::Int(size) => write!(f, "int{}", size),
+            ParamType::Address => write!(f, "address"),
+            ParamType::Bool => write!(f, "bool"),
+            ParamType::FixedBytes(size) => write!(f, "bytes{}", size),
+            ParamType::Bytes => write!(f, "bytes"),
+            ParamType::String => write!(f, "string"),
+            ParamType::Array(inner) => write!(f, "{}[]", inner),
+            ParamType::FixedArray(inner, len) => write!(f, "{}[{}]", inner, len),
+            ParamType::Tuple(members) => {
+                write!(f, "(")?;
+                write_list(f, members)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Value of a parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Token {
+    Uint(U256),
+    Int(U256),
+    Address(Address),
+    Bool(bool),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    FixedArray(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    /// Whether the token can be encoded as `kind`, including whether integers
+    /// fit into its size.
+    pub fn type_check(&self, kind: &ParamType) -> bool {
+        match (self, kind) {
+            (Token::Uint(value), ParamType::Uint(size)) => value.bits() <= *size,
+            (Token::Int(value), ParamType::Int(size)) => {
+                // The bits above the sign bit must all be copies of it.
+                let high = *value >> (*size - 1);
+                high.is_zero() || high == U256::max_value() >> (*size - 1)
+            }
+            (Token::Address(_), ParamType::Address)
+            | (Token::Bool(_), ParamType::Bool)
+            | (Token::Bytes(_), ParamType::Bytes)
+            | (Token::String(_), ParamType::String) => true,
+            (Token::FixedBytes(bytes), ParamType::FixedBytes(size)) => bytes.len() == *size,
+            (Token::Array(tokens), ParamType::Array(inner)) => {
+                tokens.iter().all(|token| token.type_check(inner))
+            }
+            (Token::FixedArray(tokens), ParamType::FixedArray(inner, len)) => {
+                tokens.len() == *len && tokens.iter().all(|token| token.type_check(inner))
+            }
+            (Token::Tuple(tokens), ParamType::Tuple(members)) => {
+                tokens.len() == members.len()
+                    && tokens.iter().zip(members).all(|(token, kind)| token.type_check(kind))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Bytes(_) | Token::String(_) | Token::Array(_) => true,
+            Token::FixedArray(tokens) | Token::Tuple(tokens) => tokens.iter().any(Token::is_dynamic),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Uint(value) => write!(f, "{}", value),
+            Token::Int(value) if value.bit(255) => write!(f, "-{}", (!*value).overflowing_add(U256::one()).0),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Address(address) => write!(f, "{:?}", address),
+            Token::Bool(value) => write!(f, "{}", value),
+            Token::FixedBytes(bytes) | Token::Bytes(bytes) => write_hex(f, bytes),
+            Token::String(value) => write!(f, "{:?}", value),
+            Token::Array(tokens) | Token::FixedArray(tokens) => {
+                write!(f, "[")?;
+                write_list(f, tokens)?;
+                write!(f, "]")
+            }
+            Token::Tuple(tokens) => {
+                write!(f, "(")?;
+                write_list(f, tokens)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamType,
+    /// Whether an event parameter is stored in the topics instead of the data.
+    pub indexed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<Param>,
+    pub outputs: Vec<Param>,
+}
+
+impl Function {
+    /// Canonical signature, like `transfer(address,uint256)`.
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.inputs)
+    }
+
+    /// First four bytes of the keccak hash of the signature.
+    pub fn selector(&self) -> [u8; 4] {
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&utils::keccak(self.signature()).as_bytes()[..4]);
+        selector
+    }
+
+    /// Call data for calling the function with `tokens`.
+    pub fn encode_input(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+        check_tokens(&self.inputs, tokens)?;
+        let mut data = self.selector().to_vec();
+        data.extend(encode(tokens));
+        Ok(data)
+    }
+
+    /// Decodes call data, selector included.
+    pub fn decode_input(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+        if data.len() < 4 || data[..4] != self.selector() {
+            return Err(Error::Abi {
+                msg: format!("Call data is not a call to {}", self.signature()),
+            });
+        }
+        decode(&param_types(&self.inputs), &data[4..])
+    }
+
+    /// Decodes the data the function returned.
+    pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>, Error> {
+        decode(&param_types(&self.outputs), data)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constructor {
+    pub inputs: Vec<Param>,
+}
+
+impl Constructor {
+    /// Arguments for deploying the contract. Unlike calls, they have no selector.
+    pub fn encode_input(&self, tokens: &[Token]) -> Result<Vec<u8>, Error> {
+        check_tokens(&self.inputs, tokens)?;
+        Ok(encode(tokens))
+    }
+}
+
+/// Parameter of a decoded event log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogParam {
+    pub name: String,
+    pub value: Token,
+}
+
+impl fmt::Display for LogParam {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.value)
+    }
+}
+
+/// Event log decoded against the event of an ABI it was emitted for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedLog {
+    pub event: String,
+    pub params: Vec<LogParam>,
+}
+
+impl fmt::Display for DecodedLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", self.event)?;
+        for (index, param) in self.params.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", param)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    pub inputs: Vec<Param>,
+    /// Anonymous events don't store their signature in the first topic.
+    pub anonymous: bool,
+}
+
+impl Event {
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.inputs)
+    }
+
+    /// Keccak hash of the signature, which is the first topic of the logs.
+    pub fn topic(&self) -> H256 {
+        utils::keccak(self.signature())
+    }
+
+    /// Decodes the parameters of `log` in the order they are declared.
+    ///
+    /// Indexed parameters which are not of a value type, like strings, arrays
+    /// and tuples, only have their hash in the topics, so they are returned as
+    /// `Token::FixedBytes` holding the hash.
+    pub fn decode_log(&self, log: &LogEntry) -> Result<Vec<LogParam>, Error> {
+        let mut topics = log.topics.iter();
+        if !self.anonymous {
+            match topics.next() {
+                Some(topic) if *topic == self.topic() => {}
+                _ => {
+                    return Err(Error::Abi {
+                        msg: format!("Log is not a {} event", self.signature()),
+                    })
+                }
+            }
+        }
+
+        let data_types: Vec<ParamType> = self
+            .inputs
+            .iter()
+            .filter(|param| !param.indexed)
+            .map(|param| param.kind.clone())
+            .collect();
+        let mut data = decode(&data_types, &log.data)?.into_iter();
+
+        let mut params = Vec::with_capacity(self.inputs.len());
+        for param in &self.inputs {
+            let value = if param.indexed {
+                let topic = topics.next().ok_or_else(|| Error::Abi {
+                    msg: format!("Log has no topic for {}", param.name),
+                })?;
+                if param.kind.is_value_type() {
+                    decode(slice::from_ref(&param.kind), topic.as_bytes())?.remove(0)
+                } else {
+                    Token::FixedBytes(topic.as_bytes().to_vec())
+                }
+            } else {
+                data.next().expect("One token is decoded for each data parameter; qed")
+            };
+            params.push(LogParam {
+                name: param.name.clone(),
+                value,
+            });
+        }
+
+        if topics.next().is_some() {
+            return Err(Error::Abi {
+                msg: format!("Log has more topics than {} has indexed parameters", self.signature()),
+            });
+        }
+
+        Ok(params)
+    }
+}
+
+/// Interface of a contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Abi {
+    pub constructor: Option<Constructor>,
+    pub functions: Vec<Function>,
+    pub events: Vec<Event>,
+}
+
+impl Abi {
+    /// Parses the JSON array solc and other compilers emit.
+    pub fn from_json(json: &str) -> Result<Abi, Error> {
+        let entries: Vec<RawEntry> = serde_json::from_str(json).map_err(|e| Error::Abi {
+            msg: format!("Malformed ABI: {}", e),
+        })?;
+
+        let mut abi = Abi::default();
+        for entry in entries {
+            match entry.kind.as_str() {
+                "function" => abi.functions.push(Function {
+                    name: entry_name(entry.name, &entry.kind)?,
+                    inputs: params(entry.inputs)?,
+                    outputs: params(entry.outputs)?,
+                }),
+                "event" => abi.events.push(Event {
+                    name: entry_name(entry.name, &entry.kind)?,
+                    inputs: params(entry.inputs)?,
+                    anonymous: entry.anonymous,
+                }),
+                "constructor" => {
+                    abi.constructor = Some(Constructor {
+                        inputs: params(entry.inputs)?,
+                    })
+                }
+                // Fallback and receive functions can't be called by name, and
+                // errors or kinds added by later compilers are of no use here.
+                _ => {}
+            }
+        }
+
+        Ok(abi)
+    }
+
+    /// First function called `name`. Overloads can be found in `functions`.
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    pub fn event(&self, name: &str) -> Option<&Event> {
+        self.events.iter().find(|event| event.name == name)
+    }
+
+    /// Decodes `log` against the event whose signature is its first topic.
+    ///
+    /// Anonymous events have no signature to match, so they are tried in
+    /// order once no other event matched.
+    pub fn decode_log(&self, log: &LogEntry) -> Result<DecodedLog, Error> {
+        let decoded = |event: &Event| {
+            event.decode_log(log).map(|params| DecodedLog {
+                event: event.name.clone(),
+                params,
+            })
+        };
+
+        if let Some(topic) = log.topics.first() {
+            let named = self
+                .events
+                .iter()
+                .find(|event| !event.anonymous && event.topic() == *topic);
+            if let Some(event) = named {
+                return decoded(event);
+            }
+        }
+
+        self.events
+            .iter()
+            .filter(|event| event.anonymous)
+            .filter_map(|event| decoded(event).ok())
+            .next()
+            .ok_or_else(|| Error::Abi {
+                msg: "Log matches no event of the ABI".to_string(),
+            })
+    }
+}
+
+/// Entry of an ABI JSON array. Which fields are set depends on `kind`.
+#[derive(Deserialize)]
+struct RawEntry {
+    /// Older compilers leave the type of functions out.
+    #[serde(rename = "type", default = "function_kind")]
+    kind: String,
+    name: Option<String>,
+    #[serde(default)]
+    inputs: Vec<RawParam>,
+    #[serde(default)]
+    outputs: Vec<RawParam>,
+    #[serde(default)]
+    anonymous: bool,
+}
+
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+#[derive(Deserialize)]
+struct RawParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    components: Vec<RawParam>,
+    #[serde(default)]
+    indexed: bool,
+}
+
+fn entry_name(name: Option<String>, kind: &str) -> Result<String, Error> {
+    name.ok_or_else(|| Error::Abi {
+        msg: format!("Malformed ABI: {} without a name", kind),
+    })
+}
+
+fn params(raw: Vec<RawParam>) -> Result<Vec<Param>, Error> {
+    raw.into_iter().map(param).collect()
+}
+
+fn param(raw: RawParam) -> Result<Param, Error> {
+    let components = params(raw.components)?
+        .into_iter()
+        .map(|param| param.kind)
+        .collect::<Vec<_>>();
+    Ok(Param {
+        name: raw.name,
+        kind: ParamType::parse(&raw.kind, &components)?,
+        indexed: raw.indexed,
+    })
+}
+
+fn param_types(params: &[Param]) -> Vec<ParamType> {
+    params.iter().map(|param| param.kind.clone()).collect()
+}
+
+fn signature(name: &str, params: &[Param]) -> String {
+    let types: Vec<String> = params.iter().map(|param| param.kind.to_string()).collect();
+    format!("{}({})", name, types.join(","))
+}
+
+fn check_tokens(params: &[Param], tokens: &[Token]) -> Result<(), Error> {
+    if params.len() != tokens.len() {
+        return Err(Error::Abi {
+            msg: format!("Expected {} arguments, got {}", params.len(), tokens.len()),
+        });
+    }
+    for (param, token) in params.iter().zip(tokens) {
+        if !token.type_check(&param.kind) {
+            return Err(Error::Abi {
+                msg: format!("Argument {} is not a {}", param.name, param.kind),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Encodes `tokens` like the members of a tuple.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let heads_len: usize = tokens.iter().map(head_len).sum();
+    let mut heads = Vec::with_capacity(heads_len);
+    let mut tails = Vec::new();
+    for token in tokens {
+        if token.is_dynamic() {
+            heads.extend_from_slice(&word(U256::from(heads_len + tails.len())));
+            tails.extend(encode_token(token));
+        } else {
+            heads.extend(encode_token(token));
+        }
+    }
+    heads.extend(tails);
+    heads
+}
+
+fn head_len(token: &Token) -> usize {
+    match token {
+        _ if token.is_dynamic() => 32,
+        Token::FixedArray(tokens) | Token::Tuple(tokens) => tokens.iter().map(head_len).sum(),
+        _ => 32,
+    }
+}
+
+fn encode_token(token: &Token) -> Vec<u8> {
+    match token {
+        Token::Uint(value) | Token::Int(value) => word(*value).to_vec(),
+        Token::Address(address) => {
+            let mut data = vec![0u8; 32];
+            data[12..].copy_from_slice(address.as_bytes());
+            data
+        }
+        Token::Bool(value) => word(U256::from(*value as u8)).to_vec(),
+        Token::FixedBytes(bytes) => padded(bytes),
+        Token::Bytes(bytes) => {
+            let mut data = word(U256::from(bytes.len())).to_vec();
+            data.extend(padded(bytes));
+            data
+        }
+        Token::String(value) => encode_token(&Token::Bytes(value.as_bytes().to_vec())),
+        Token::Array(tokens) => {
+            let mut data = word(U256::from(tokens.len())).to_vec();
+            data.extend(encode(tokens));
+            data
+        }
+        Token::FixedArray(tokens) | Token::Tuple(tokens) => encode(tokens),
+    }
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut data = [0u8; 32];
+    value.to_big_endian(&mut data);
+    data
+}
+
+/// Right pads `bytes` with zeros to a multiple of 32 bytes.
+fn padded(bytes: &[u8]) -> Vec<u8> {
+    let mut data = bytes.to_vec();
+    data.resize(bytes.len().div_ceil(32) * 32, 0);
+    data
+}
+
+/// Decodes data encoded like the members of a tuple of `types`.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>, Error> {
+    let mut offset = 0;
+    let mut tokens = Vec::with_capacity(types.len());
+    for kind in types {
+        if kind.is_dynamic() {
+            let tail = read_usize(data, offset)?;
+            tokens.push(decode_token(kind, data, tail)?);
+        } else {
+            tokens.push(decode_token(kind, data, offset)?);
+        }
+        offset += kind.head_len();
+    }
+    Ok(tokens)
+}
+
+/// Decodes a token whose encoding starts at `offset` of `data`.
+fn decode_token(kind: &ParamType, data: &[u8], offset: usize) -> Result<Token, Error> {
+    let token = match kind {
+        ParamType::Uint(_) => Token::Uint(U256::from_big_endian(read(data, offset, 32)?)),
+        ParamType::Int(_) => Token::Int(U256::from_big_endian(read(data, offset, 32)?)),
+        ParamType::Address => Token::Address(Address::from_slice(&read(data, offset, 32)?[12..])),
+        ParamType::Bool => match read_usize(data, offset)? {
+            0 => Token::Bool(false),
+            1 => Token::Bool(true),
+            _ => return Err(malformed("bool is neither 0 nor 1")),
+        },
+        ParamType::FixedBytes(size) => Token::FixedBytes(read(data, offset, 32)?[..*size].to_vec()),
+        ParamType::Bytes => Token::Bytes(read_bytes(data, offset)?),
+        ParamType::String => {
+            let bytes = read_bytes(data, offset)?;
+            Token::String(String::from_utf8(bytes).map_err(|_| Error::BadUtf8)?)
+        }
+        ParamType::Array(inner) => {
+            let len = read_usize(data, offset)?;
+            let start = offset + 32;
+            // Every element takes at least one word, which bounds the allocation.
+            if len > data.len().saturating_sub(start) / 32 {
+                return Err(malformed("array is longer than the data"));
+            }
+            let types = vec![(**inner).clone(); len];
+            Token::Array(decode(&types, &data[start..])?)
+        }
+        ParamType::FixedArray(inner, len) => {
+            let types = vec![(**inner).clone(); *len];
+            Token::FixedArray(decode(&types, slice_from(data, offset)?)?)
+        }
+        ParamType::Tuple(members) => Token::Tuple(decode(members, slice_from(data, offset)?)?),
+    };
+    Ok(token)
+}
+
+fn read(data: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| malformed("data is too short"))
+}
+
+fn slice_from(data: &[u8], offset: usize) -> Result<&[u8], Error> {
+    data.get(offset..).ok_or_else(|| malformed("offset is out of bounds"))
+}
+
+fn read_usize(data: &[u8], offset: usize) -> Result<usize, Error> {
+    let value = U256::from_big_endian(read(data, offset, 32)?);
+    if value > U256::from(data.len()) {
+        return Err(malformed("offset or length is out of bounds"));
+    }
+    Ok(value.low_u64() as usize)
+}
+
+fn read_bytes(data: &[u8], offset: usize) -> Result<Vec<u8>, Error> {
+    let len = read_usize(data, offset)?;
+    Ok(read(data, offset + 32, len)?.to_vec())
+}
+
+fn malformed(what: &str) -> Error {
+    Error::Abi {
+        msg: format!("Malformed data: {}", what),
+    }
+}
+
+fn bad_type(name: &str) -> Error {
+    Error::Abi {
+        msg: format!("Unknown type {}", name),
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, items: &[T]) -> fmt::Result {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+fn write_hex(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    write!(f, "0x")?;
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(words: &[&str]) -> Vec<u8> {
+        let hex: String = words.concat();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn function(signature: &str) -> Function {
+        let open = signature.find('(').unwrap();
+        let types = &signature[open + 1..signature.len() - 1];
+        Function {
+            name: signature[..open].to_string(),
+            inputs: split_types(types)
+                .iter()
+                .map(|kind| Param {
+                    name: String::new(),
+                    kind: ParamType::parse(kind, &[]).unwrap(),
+                    indexed: false,
+                })
+                .collect(),
+            outputs: vec![],
+        }
+    }
+
+    fn split_types(types: &str) -> Vec<&str> {
+        if types.is_empty() {
+            vec![]
+        } else {
+            types.split(',').collect()
+        }
+    }
+
+    fn uint(value: u64) -> Token {
+        Token::Uint(U256::from(value))
+    }
+
+    /// Examples of the Solidity ABI specification.
+    #[test]
+    fn encodes_solidity_examples() {
+        let baz = function("baz(uint32,bool)");
+        assert_eq!(
+            baz.encode_input(&[uint(69), Token::Bool(true)]).unwrap(),
+            hex(&[
+                "cdcd77c0",
+                "0000000000000000000000000000000000000000000000000000000000000045",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+            ])
+        );
+
+        let bar = function("bar(bytes3[2])");
+        let tokens = [Token::FixedArray(vec![
+            Token::FixedBytes(b"abc".to_vec()),
+            Token::FixedBytes(b"def".to_vec()),
+        ])];
+        assert_eq!(
+            bar.encode_input(&tokens).unwrap(),
+            hex(&[
+                "fce353f6",
+                "6162630000000000000000000000000000000000000000000000000000000000",
+                "6465660000000000000000000000000000000000000000000000000000000000",
+            ])
+        );
+
+        let sam = function("sam(bytes,bool,uint256[])");
+        let tokens = [
+            Token::Bytes(b"dave".to_vec()),
+            Token::Bool(true),
+            Token::Array(vec![uint(1), uint(2), uint(3)]),
+        ];
+        let encoded = hex(&[
+            "a5643bf2",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "6461766500000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+        ]);
+        assert_eq!(sam.encode_input(&tokens).unwrap(), encoded);
+        assert_eq!(sam.decode_input(&encoded).unwrap(), tokens.to_vec());
+
+        let f = function("f(uint256,uint32[],bytes10,bytes)");
+        let tokens = [
+            uint(0x123),
+            Token::Array(vec![uint(0x456), uint(0x789)]),
+            Token::FixedBytes(b"1234567890".to_vec()),
+            Token::Bytes(b"Hello, world!".to_vec()),
+        ];
+        let encoded = hex(&[
+            "8be65246",
+            "0000000000000000000000000000000000000000000000000000000000000123",
+            "0000000000000000000000000000000000000000000000000000000000000080",
+            "3132333435363738393000000000000000000000000000000000000000000000",
+            "00000000000000000000000000000000000000000000000000000000000000e0",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000456",
+            "0000000000000000000000000000000000000000000000000000000000000789",
+            "000000000000000000000000000000000000000000000000000000000000000d",
+            "48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+        ]);
+        assert_eq!(f.encode_input(&tokens).unwrap(), encoded);
+        assert_eq!(f.decode_input(&encoded).unwrap(), tokens.to_vec());
+    }
+
+    #[test]
+    fn known_selectors_and_topics() {
+        assert_eq!(function("transfer(address,uint256)").selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let abi = Abi::from_json(
+            r#"[{"type": "event", "name": "Transfer", "inputs": [
+                {"name": "from", "type": "address", "indexed": true},
+                {"name": "to", "type": "address", "indexed": true},
+                {"name": "value", "type": "uint256"}
+            ]}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            abi.event("Transfer").unwrap().topic().as_bytes(),
+            &hex(&["ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"])[..]
+        );
+    }
+
+    #[test]
+    fn rejects_integers_wider_than_their_type() {
+        assert!(uint(255).type_check(&ParamType::Uint(8)));
+        assert!(!uint(256).type_check(&ParamType::Uint(8)));
+        assert!(Token::Uint(U256::max_value()).type_check(&ParamType::Uint(256)));
+
+        let int = |value: i64| {
+            let value = if value < 0 {
+                !U256::from(-value - 1)
+            } else {
+                U256::from(value)
+            };
+            Token::Int(value)
+        };
+        assert!(int(127).type_check(&ParamType::Int(8)));
+        assert!(int(-128).type_check(&ParamType::Int(8)));
+        assert!(!int(128).type_check(&ParamType::Int(8)));
+        assert!(!int(-129).type_check(&ParamType::Int(8)));
+        assert!(int(-1).type_check(&ParamType::Int(256)));
+
+        let baz = function("baz(uint32,bool)");
+        assert!(baz.encode_input(&[uint(1 << 32), Token::Bool(true)]).is_err());
+    }
+
+    #[test]
+    fn parses_entries_without_type_and_skips_unknown_kinds() {
+        let abi = Abi::from_json(
+            r#"[
+                {"name": "get", "inputs": [], "outputs": [{"name": "", "type": "uint256"}]},
+                {"type": "error", "name": "Unauthorized", "inputs": []},
+                {"type": "fallback"},
+                {"type": "receive", "stateMutability": "payable"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(abi.functions.len(), 1);
+        assert_eq!(abi.function("get").unwrap().signature(), "get()");
+        assert!(abi.events.is_empty());
+
+        assert!(Abi::from_json(r#"[{"type": "function", "inputs": []}]"#).is_err());
+    }
+
+    #[test]
+    fn indexed_fixed_arrays_are_hashed() {
+        let abi = Abi::from_json(
+            r#"[{"type": "event", "name": "Pair", "inputs": [
+                {"name": "pair", "type": "uint256[2]", "indexed": true},
+                {"name": "owner", "type": "address", "indexed": true},
+                {"name": "amount", "type": "uint256"}
+            ]}]"#,
+        )
+        .unwrap();
+        let event = abi.event("Pair").unwrap();
+        assert_eq!(event.signature(), "Pair(uint256[2],address,uint256)");
+
+        let pair = utils::keccak(encode(&[Token::FixedArray(vec![uint(1), uint(2)])]));
+        let owner = Address::repeat_byte(0xaa);
+        let log = LogEntry {
+            address: Address::repeat_byte(1),
+            topics: vec![event.topic(), pair, H256::from(owner)],
+            data: encode(&[uint(5)]),
+        };
+
+        let decoded = abi.decode_log(&log).unwrap();
+        let values: Vec<Token> = decoded.params.into_iter().map(|param| param.value).collect();
+        assert_eq!(
+            values,
+            vec![
+                Token::FixedBytes(pair.as_bytes().to_vec()),
+                Token::Address(owner),
+                uint(5),
+            ]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use address::Address;
+use primitive_types::H256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Access {
+    /// Entry was read but left untouched.
+    Read,
+    /// Entry was written. Written entries are always read first.
+    Write,
+}
+
+/// Accounts and storage slots a transaction touched, sorted by address and key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessReport {
+    pub accounts: Vec<(Address, Access)>,
+    pub storage: Vec<(Address, H256, Access)>,
+    /// Variable-length storage values, keyed apart from the 32-byte slots.
+    pub storage_bytes: Vec<(Address, H256, Access)>,
+}
+
+impl AccessReport {
+    pub fn written_accounts<'a>(&'a self) -> impl Iterator<Item = &'a Address> + 'a {
+        self.accounts
+            .iter()
+            .filter(|(_, access)| *access == Access::Write)
+            .map(|(address, _)| address)
+    }
+
+    pub fn written_storage<'a>(&'a self) -> impl Iterator<Item = (&'a Address, &'a H256)> + 'a {
+        self.storage
+            .iter()
+            .filter(|(_, _, access)| *access == Access::Write)
+            .map(|(address, key, _)| (address, key))
+    }
+
+    pub fn written_storage_bytes<'a>(&'a self) -> impl Iterator<Item = (&'a Address, &'a H256)> + 'a {
+        self.storage_bytes
+            .iter()
+            .filter(|(_, _, access)| *access == Access::Write)
+            .map(|(address, key, _)| (address, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{self, MemoryProvider};
+
+    #[test]
+    fn reports_callee_read() {
+        let contract = Address::repeat_byte(1);
+        let mut provider = MemoryProvider::default();
+        provider.deploy(contract, r#"(module (func (export "call")))"#);
+
+        let outcome = testing::execute(&mut provider, &testing::call(contract, vec![]));
+        assert!(outcome.is_success());
+        assert_eq!(outcome.accesses().accounts, vec![(contract, Access::Read)]);
+    }
+}
@@ -0,0 +1,289 @@
+use parity_wasm::builder;
+use parity_wasm::elements::{self, BlockType, Instruction, Internal, Type};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Host function a traced function calls with its index when it is entered.
+pub(crate) const ENTER_FRAME: &str = "__enter_frame";
+/// Host function a traced function calls right before it returns.
+pub(crate) const LEAVE_FRAME: &str = "__leave_frame";
+
+/// Whether `field` names one of the frame tracer functions, which contracts may not import.
+pub(crate) fn is_reserved(field: &str) -> bool {
+	field == ENTER_FRAME || field == LEAVE_FRAME
+}
+
+/// Function of the contract which was on the call stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+	/// Index of the function in the module as it was deployed.
+	pub index: u32,
+	/// Name of the function from the module's `name` section, if it has one.
+	pub name: Option<String>,
+}
+
+/// Call stack of a contract, innermost frame first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backtrace {
+	pub frames: Vec<Frame>,
+}
+
+impl Backtrace {
+	pub fn is_empty(&self) -> bool {
+		self.frames.is_empty()
+	}
+}
+
+impl fmt::Display for Backtrace {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (depth, frame) in self.frames.iter().enumerate() {
+			if depth > 0 {
+				writeln!(f)?;
+			}
+			match frame.name {
+				Some(ref name) => write!(f, "{:>4}: {} (#{})", depth, name, frame.index)?,
+				None => write!(f, "{:>4}: <unnamed> (#{})", depth, frame.index)?,
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Function names of the module, by function index.
+///
+/// Modules without a `name` section, or with one that can't be decoded, have no names.
+pub(crate) fn function_names(module: &elements::Module) -> BTreeMap<u32, String> {
+	let module = match module.clone().parse_names() {
+		Ok(module) => module,
+		Err((_, module)) => module,
+	};
+
+	module
+		.names_section()
+		.and_then(|names| names.functions())
+		.map(|functions| {
+			functions
+				.names()
+				.iter()
+				.map(|(index, name)| (index, name.clone()))
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+/// Functions of a deployed module which get traced.
+///
+/// Taken before the module is instrumented, because instrumentation adds imports
+/// which shift the function indices, and helper functions which are not traced.
+#[derive(Debug, Clone)]
+pub(crate) struct Traced {
+	imported: u32,
+	defined: u32,
+	names: BTreeMap<u32, String>,
+}
+
+impl Traced {
+	pub(crate) fn new(module: &elements::Module) -> Self {
+		Traced {
+			imported: module.import_count(elements::ImportCountType::Function) as u32,
+			defined: module.function_section().map_or(0, |fs| fs.entries().len() as u32),
+			names: function_names(module),
+		}
+	}
+
+	/// Resolves the indices pushed by `__enter_frame` to a backtrace, innermost first.
+	pub(crate) fn backtrace(&self, call_stack: &[u32]) -> Backtrace {
+		Backtrace {
+			frames: call_stack
+				.iter()
+				.rev()
+				.map(|index| Frame {
+					index: *index,
+					name: self.names.get(index).cloned(),
+				})
+				.collect(),
+		}
+	}
+}
+
+/// Injects calls to `__enter_frame` and `__leave_frame` into every function
+/// the module had when it was deployed.
+///
+/// Must run after all other instrumentation, so the calls are neither metered
+/// nor counted by the stack height limiter.
+pub(crate) fn inject_frame_tracer(module: elements::Module, traced: &Traced) -> elements::Module {
+	let mut mbuilder = builder::from_module(module);
+	let enter_sig = mbuilder.push_signature(builder::signature().param().i32().build_sig());
+	let leave_sig = mbuilder.push_signature(builder::signature().build_sig());
+	mbuilder.push_import(
+		builder::import()
+			.module("env")
+			.field(ENTER_FRAME)
+			.external()
+			.func(enter_sig)
+			.build(),
+	);
+	mbuilder.push_import(
+		builder::import()
+			.module("env")
+			.field(LEAVE_FRAME)
+			.external()
+			.func(leave_sig)
+			.build(),
+	);
+	let mut module = mbuilder.build();
+
+	let leave_func = module.import_count(elements::ImportCountType::Function) as u32 - 1;
+	let enter_func = leave_func - 1;
+	let shift = |index: &mut u32| {
+		if *index >= enter_func {
+			*index += 2
+		}
+	};
+
+	let result_types: Vec<BlockType> = {
+		let types = module.type_section().map_or(&[][..], |ts| ts.types());
+		module
+			.function_section()
+			.map_or(&[][..], |fs| fs.entries())
+			.iter()
+			.map(|func| match types.get(func.type_ref() as usize) {
+				Some(Type::Function(ty)) => match ty.return_type() {
+					Some(value_type) => BlockType::Value(value_type),
+					None => BlockType::NoResult,
+				},
+				None => BlockType::NoResult,
+			})
+			.collect()
+	};
+
+	let start = module.start_section();
+	for section in module.sections_mut() {
+		match section {
+			elements::Section::Code(code_section) => {
+				for (position, body) in code_section.bodies_mut().iter_mut().enumerate() {
+					for instruction in body.code_mut().elements_mut().iter_mut() {
+						if let Instruction::Call(index) = instruction {
+							shift(index);
+						}
+					}
+
+					if (position as u32) < traced.defined {
+						let original_index = traced.imported + position as u32;
+						let block_type = result_types[position];
+						trace_body(body.code_mut(), original_index, block_type, enter_func, leave_func);
+					}
+				}
+			}
+			elements::Section::Export(export_section) => {
+				for export in export_section.entries_mut() {
+					if let Internal::Function(index) = export.internal_mut() {
+						shift(index);
+					}
+				}
+			}
+			elements::Section::Element(elements_section) => {
+				for segment in elements_section.entries_mut() {
+					for index in segment.members_mut() {
+						shift(index);
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	if let Some(mut start) = start {
+		shift(&mut start);
+		module.set_start_section(start);
+	}
+
+	module
+}
+
+/// Wraps the body into a block so branches to the function label still pass the
+/// `__leave_frame` call at its end, and adds one before every `return`.
+fn trace_body(
+	code: &mut elements::Instructions,
+	index: u32,
+	block_type: BlockType,
+	enter_func: u32,
+	leave_func: u32,
+) {
+	let body = code.elements_mut();
+	let mut traced = Vec::with_capacity(body.len() + 6);
+	traced.push(Instruction::I32Const(index as i32));
+	traced.push(Instruction::Call(enter_func));
+	traced.push(Instruction::Block(block_type));
+	for instruction in body.drain(..) {
+		if let Instruction::Return = instruction {
+			traced.push(Instruction::Call(leave_func));
+		}
+		traced.push(instruction);
+	}
+	// The final `end` of the body now closes the block.
+	traced.push(Instruction::Call(leave_func));
+	traced.push(Instruction::End);
+	*body = traced;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use address::Address;
+	use block::BlockEnv;
+	use error::Error;
+	use execute::{self, ExecuteOptions, ExecutionOutcome};
+	use testing::{self, MemoryProvider};
+
+	const TRAP: &str = r#"
+		(module
+			(import "env" "memory" (memory 1 16))
+			(func $fail unreachable)
+			(func (export "call") (call $fail)))
+	"#;
+
+	fn backtrace(options: &ExecuteOptions) -> Backtrace {
+		let contract = Address::repeat_byte(1);
+		let mut provider = MemoryProvider::default();
+		provider.deploy(contract, TRAP);
+		let transaction = testing::call(contract, vec![]);
+		match execute::execute_with_options(&mut provider, &BlockEnv::default(), &transaction, options) {
+			Ok(ExecutionOutcome::Trap { diagnostics, .. }) => diagnostics.backtrace,
+			outcome => panic!("Expected a trap, got {:?}", outcome),
+		}
+	}
+
+	#[test]
+	fn traces_only_on_request() {
+		assert!(backtrace(&ExecuteOptions::default()).is_empty());
+
+		let options = ExecuteOptions {
+			backtrace: true,
+			..Default::default()
+		};
+		let indices: Vec<u32> = backtrace(&options).frames.iter().map(|frame| frame.index).collect();
+		assert_eq!(indices, vec![0, 1]);
+	}
+
+	#[test]
+	fn rejects_reserved_imports() {
+		let contract = Address::repeat_byte(1);
+		let mut provider = MemoryProvider::default();
+		provider.deploy(
+			contract,
+			r#"
+			(module
+				(import "env" "memory" (memory 1 16))
+				(import "env" "__enter_frame" (func $enter (param i32)))
+				(func (export "call") (call $enter (i32.const 0))))
+		"#,
+		);
+
+		let transaction = testing::call(contract, vec![]);
+		match execute::execute(&mut provider, &BlockEnv::default(), &transaction) {
+			Err(Error::Wasm { msg }) => assert!(msg.contains(ENTER_FRAME)),
+			outcome => panic!("Expected the module to be rejected, got {:?}", outcome),
+		}
+	}
+}
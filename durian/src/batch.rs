@@ -0,0 +1,190 @@
+//! Optimistic parallel execution of a batch of transactions.
+//!
+//! All transactions first run in parallel against the same snapshot. They are
+//! then validated in order: a transaction which read anything an earlier
+//! transaction of the batch wrote is executed again on top of the changes
+//! committed so far. The outcome is the same as executing them one by one.
+
+use access::AccessReport;
+use address::Address;
+use block::BlockEnv;
+use error::Error;
+use execute::{execute, ExecutionOutcome};
+use overlay::{Overlay, StateDiff};
+use primitive_types::H256;
+use provider::Provider;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use transaction::Transaction;
+
+struct Speculation {
+    result: Result<ExecutionOutcome, Error>,
+    diff: StateDiff,
+}
+
+/// Executes `transactions` of the block described by `env` on `workers` threads
+/// and applies their changes to `provider`.
+///
+/// Returns the outcome of each transaction, in order. The outer error is only
+/// returned if the changes can't be written back to the provider.
+pub fn execute_batch<P: Provider + Sync>(
+    provider: &mut P,
+    env: &BlockEnv,
+    transactions: &[Transaction],
+    workers: usize,
+) -> Result<Vec<Result<ExecutionOutcome, Error>>, Error> {
+    let speculations = speculate(&*provider, env, transactions, workers);
+
+    let mut committed = StateDiff::default();
+    let mut written = WriteSet::default();
+    let mut results = Vec::with_capacity(transactions.len());
+
+    for (index, (transaction, speculation)) in transactions.iter().zip(speculations).enumerate() {
+        let speculation = if written.conflicts_with(&speculation.result) {
+            trace!("Re-executing conflicting transaction {}", index);
+            let base = Overlay::with_diff(&*provider, committed.clone());
+            let mut overlay = Overlay::new(&base);
+            let result = execute(&mut overlay, env, transaction);
+            Speculation {
+                result,
+                diff: overlay.into_diff(),
+            }
+        } else {
+            speculation
+        };
+
+        written.add(&speculation.diff);
+        committed.merge(speculation.diff);
+        results.push(speculation.result);
+    }
+
+    committed.apply(provider)?;
+
+    Ok(results)
+}
+
+fn speculate<P: Provider + Sync>(
+    provider: &P,
+    env: &BlockEnv,
+    transactions: &[Transaction],
+    workers: usize,
+) -> Vec<Speculation> {
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<Speculation>>> =
+        transactions.iter().map(|_| Mutex::new(None)).collect();
+
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1).min(transactions.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let transaction = match transactions.get(index) {
+                    Some(transaction) => transaction,
+                    None => break,
+                };
+
+                let mut overlay = Overlay::new(provider);
+                let result = execute(&mut overlay, env, transaction);
+                let speculation = Speculation {
+                    result,
+                    diff: overlay.into_diff(),
+                };
+                *slots[index].lock().expect("No thread panics while holding the lock; qed") =
+                    Some(speculation);
+            });
+        }
+    });
+
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .expect("No thread panics while holding the lock; qed")
+                .expect("Every transaction is executed by a worker; qed")
+        })
+        .collect()
+}
+
+/// Accounts and storage slots written by the transactions validated so far.
+#[derive(Default)]
+struct WriteSet {
+    accounts: BTreeSet<Address>,
+    storage: BTreeSet<(Address, H256)>,
+    storage_bytes: BTreeSet<(Address, H256)>,
+}
+
+impl WriteSet {
+    fn add(&mut self, diff: &StateDiff) {
+        self.accounts.extend(diff.created.keys().cloned());
+        self.accounts.extend(diff.updated.keys().cloned());
+        for (addr, storage) in &diff.storage {
+            self.storage.extend(storage.keys().map(|key| (*addr, *key)));
+        }
+        for (addr, storage) in &diff.storage_bytes {
+            self.storage_bytes
+                .extend(storage.keys().map(|key| (*addr, *key)));
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.storage.is_empty() && self.storage_bytes.is_empty()
+    }
+
+    fn conflicts_with(&self, result: &Result<ExecutionOutcome, Error>) -> bool {
+        match result {
+            Ok(outcome) => self.overlaps(outcome.accesses()),
+            // A transaction which could not be executed doesn't tell what it
+            // read, so it is only trusted if nothing was written before it.
+            Err(_) => !self.is_empty(),
+        }
+    }
+
+    fn overlaps(&self, accesses: &AccessReport) -> bool {
+        accesses
+            .accounts
+            .iter()
+            .any(|(addr, _)| self.accounts.contains(addr))
+            || accesses
+                .storage
+                .iter()
+                .any(|(addr, key, _)| self.storage.contains(&(*addr, *key)))
+            || accesses
+                .storage_bytes
+                .iter()
+                .any(|(addr, key, _)| self.storage_bytes.contains(&(*addr, *key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{self, MemoryProvider};
+
+    /// Increments the first byte of slot 0.
+    const COUNTER: &str = r#"
+        (module
+            (import "env" "memory" (memory 1 16))
+            (import "env" "storage_read" (func $read (param i32 i32)))
+            (import "env" "storage_write" (func $write (param i32 i32)))
+            (func (export "call")
+                (call $read (i32.const 0) (i32.const 32))
+                (i32.store8 (i32.const 32) (i32.add (i32.load8_u (i32.const 32)) (i32.const 1)))
+                (call $write (i32.const 0) (i32.const 32))))
+    "#;
+
+    #[test]
+    fn reexecutes_conflicting_transactions() {
+        let counter = Address::repeat_byte(1);
+        let mut provider = MemoryProvider::default();
+        provider.deploy(counter, COUNTER);
+
+        let transactions = vec![testing::call(counter, vec![]); 3];
+        let results = execute_batch(&mut provider, &BlockEnv::default(), &transactions, 3).unwrap();
+        assert!(results.iter().all(|result| result.as_ref().unwrap().is_success()));
+
+        // Run one by one, each transaction sees the increment of the one before.
+        let value = provider.storage_at(&counter, &H256::zero()).unwrap();
+        assert_eq!(value.as_bytes()[0], 3);
+    }
+}
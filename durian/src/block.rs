@@ -0,0 +1,96 @@
+use address::Address;
+use error::Error;
+use execute::{execute, ExecutionOutcome};
+use overlay::Overlay;
+use primitive_types::U256;
+use provider::Provider;
+use receipt::{Receipt, ReceiptStatus};
+use transaction::{Action, Transaction};
+
+/// Context of the block the transactions are executed in.
+///
+/// Fields missing from a serialized environment default to zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockEnv {
+    pub number: u64,
+    pub timestamp: u64,
+    pub author: Address,
+    pub difficulty: U256,
+    pub gas_limit: U256,
+    pub base_fee: U256,
+    pub chain_id: U256,
+}
+
+/// Executes `transactions` in order as one block and applies their changes to `provider`.
+///
+/// Contracts see the block context from `env`. A transaction whose gas does not
+/// fit into what is left of the block gas limit is skipped. A failed transaction
+/// is charged the gas it used and its changes are discarded; one which could not
+/// be executed at all is charged all its gas. Returns one receipt per transaction.
+pub fn execute_block(
+    provider: &mut dyn Provider,
+    env: &BlockEnv,
+    transactions: &[Transaction],
+) -> Result<Vec<Receipt>, Error> {
+    let (diff, receipts) = {
+        let mut overlay = Overlay::new(&*provider);
+        let mut cumulative_gas_used = U256::zero();
+        let mut receipts = Vec::with_capacity(transactions.len());
+
+        for transaction in transactions {
+            if transaction.gas > env.gas_limit - cumulative_gas_used {
+                debug!("Transaction does not fit into the block gas limit");
+                receipts.push(Receipt::new(
+                    ReceiptStatus::Skipped,
+                    U256::zero(),
+                    cumulative_gas_used,
+                    vec![],
+                    None,
+                ));
+                continue;
+            }
+
+            let receipt = match execute(&mut overlay, env, transaction) {
+                Ok(ExecutionOutcome::Success { gas_used, result }) => {
+                    cumulative_gas_used += gas_used;
+                    let contract = match transaction.action {
+                        Action::Create(_, _) => Some(result.contract),
+                        Action::Call(_) => None,
+                    };
+                    Receipt::new(
+                        ReceiptStatus::Succeeded,
+                        gas_used,
+                        cumulative_gas_used,
+                        result.logs,
+                        contract,
+                    )
+                }
+                Ok(outcome) => {
+                    debug!("Transaction failed: {:?}", outcome);
+                    let gas_used = outcome.gas_used();
+                    cumulative_gas_used += gas_used;
+                    Receipt::new(ReceiptStatus::Failed, gas_used, cumulative_gas_used, vec![], None)
+                }
+                Err(err) => {
+                    debug!("Transaction failed: {}", err);
+                    cumulative_gas_used += transaction.gas;
+                    Receipt::new(
+                        ReceiptStatus::Failed,
+                        transaction.gas,
+                        cumulative_gas_used,
+                        vec![],
+                        None,
+                    )
+                }
+            };
+            receipts.push(receipt);
+        }
+
+        (overlay.into_diff(), receipts)
+    };
+
+    diff.apply(provider)?;
+
+    Ok(receipts)
+}
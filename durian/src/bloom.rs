@@ -0,0 +1,199 @@
+use address::Address;
+use log_entry::LogEntry;
+use primitive_types::H256;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use std::fmt;
+use utils;
+
+pub const BLOOM_SIZE: usize = 256;
+
+/// 2048-bit bloom filter over log addresses and topics, as used by Ethereum.
+///
+/// Every input sets three bits, taken from the first six bytes of its keccak
+/// hash. A bloom which doesn't have all three bits of an input set certainly
+/// wasn't built from it; one which has them might have been.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Bloom(pub [u8; BLOOM_SIZE]);
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom([0u8; BLOOM_SIZE])
+    }
+}
+
+impl Bloom {
+    /// Bloom of the addresses and topics of `logs`.
+    pub fn from_logs(logs: &[LogEntry]) -> Self {
+        let mut bloom = Bloom::default();
+        for log in logs {
+            bloom.accrue_log(log);
+        }
+        bloom
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+
+    pub fn accrue(&mut self, input: &[u8]) {
+        for bit in bits(input).iter() {
+            self.0[index_of(*bit)] |= mask_of(*bit);
+        }
+    }
+
+    pub fn accrue_log(&mut self, log: &LogEntry) {
+        self.accrue(log.address.as_bytes());
+        for topic in &log.topics {
+            self.accrue(topic.as_bytes());
+        }
+    }
+
+    /// Adds all the inputs of `other`, e.g. to build the bloom of a block.
+    pub fn accrue_bloom(&mut self, other: &Bloom) {
+        for (byte, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *byte |= *other;
+        }
+    }
+
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        bits(input)
+            .iter()
+            .all(|bit| self.0[index_of(*bit)] & mask_of(*bit) != 0)
+    }
+
+    /// Whether every bit set in `other` is set in this bloom too.
+    pub fn contains_bloom(&self, other: &Bloom) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(byte, other)| byte & other == *other)
+    }
+
+    pub fn contains_address(&self, address: &Address) -> bool {
+        self.contains_input(address.as_bytes())
+    }
+
+    pub fn contains_topic(&self, topic: &H256) -> bool {
+        self.contains_input(topic.as_bytes())
+    }
+}
+
+/// Indices of the three bits `input` sets, counted from the last byte.
+fn bits(input: &[u8]) -> [usize; 3] {
+    let hash = utils::keccak(input);
+    let mut bits = [0usize; 3];
+    for (index, bit) in bits.iter_mut().enumerate() {
+        let pair = ((hash[2 * index] as usize) << 8) | hash[2 * index + 1] as usize;
+        *bit = pair & (BLOOM_SIZE * 8 - 1);
+    }
+    bits
+}
+
+fn index_of(bit: usize) -> usize {
+    BLOOM_SIZE - 1 - bit / 8
+}
+
+fn mask_of(bit: usize) -> u8 {
+    1 << (bit % 8)
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x")?;
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialized as a `0x` prefixed hex string, like the hash types.
+impl serde::Serialize for Bloom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:?}", self))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Bloom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BloomVisitor;
+
+        impl<'de> Visitor<'de> for BloomVisitor {
+            type Value = Bloom;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a 0x prefixed hex string of {} bytes", BLOOM_SIZE)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Bloom, E> {
+                let hex = value.trim_start_matches("0x");
+                if hex.len() != BLOOM_SIZE * 2 || !hex.is_ascii() {
+                    return Err(E::invalid_length(hex.len() / 2, &self));
+                }
+                let mut bloom = Bloom::default();
+                for (index, byte) in bloom.0.iter_mut().enumerate() {
+                    *byte = u8::from_str_radix(&hex[2 * index..2 * index + 2], 16)
+                        .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))?;
+                }
+                Ok(bloom)
+            }
+        }
+
+        deserializer.deserialize_str(BloomVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::slice;
+
+    /// A `Transfer` event of the WETH token on Ethereum mainnet.
+    fn transfer_log() -> LogEntry {
+        LogEntry {
+            address: "c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".parse().unwrap(),
+            topics: vec![
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                    .parse()
+                    .unwrap(),
+            ],
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn contains_its_inputs_only() {
+        let log = transfer_log();
+        let bloom = Bloom::from_logs(slice::from_ref(&log));
+        assert!(bloom.contains_address(&log.address));
+        assert!(bloom.contains_topic(&log.topics[0]));
+        assert!(!bloom.contains_address(&Address::repeat_byte(1)));
+        assert!(!bloom.contains_topic(&H256::repeat_byte(2)));
+
+        assert!(Bloom::default().is_empty());
+        assert!(!bloom.is_empty());
+        assert!(bloom.contains_bloom(&Bloom::default()));
+        assert!(!Bloom::default().contains_bloom(&bloom));
+    }
+
+    #[test]
+    fn matches_ethereum_bloom() {
+        let bloom = Bloom::from_logs(&[transfer_log()]);
+
+        // The non-zero bytes of the bloom, computed independently.
+        let set = [
+            (48, 0x02),
+            (52, 0x08),
+            (75, 0x08),
+            (123, 0x10),
+            (195, 0x02),
+            (226, 0x20),
+        ];
+        let mut expected = Bloom::default();
+        for (index, byte) in set.iter() {
+            expected.0[*index] = *byte;
+        }
+        assert_eq!(bloom, expected);
+    }
+}
@@ -0,0 +1,286 @@
+use address::Address;
+use error::Error;
+use overlay::StateDiff;
+use primitive_types::{H256, U256};
+use rlp::{self, Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use std::collections::BTreeMap;
+use trie::{self, Trie};
+use utils;
+use witness::Witness;
+
+/// Account as it is committed to in the state trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieAccount {
+    pub nonce: U256,
+    pub balance: U256,
+    pub storage_root: H256,
+    pub code_hash: H256,
+}
+
+impl Encodable for TrieAccount {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(4);
+        s.append(&self.nonce);
+        s.append(&self.balance);
+        s.append(&self.storage_root);
+        s.append(&self.code_hash);
+    }
+}
+
+impl Decodable for TrieAccount {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(TrieAccount {
+            nonce: rlp.val_at(0)?,
+            balance: rlp.val_at(1)?,
+            storage_root: rlp.val_at(2)?,
+            code_hash: rlp.val_at(3)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpAccount {
+    pub nonce: U256,
+    pub balance: U256,
+    pub code: Vec<u8>,
+    pub storage: BTreeMap<H256, H256>,
+    pub storage_bytes: BTreeMap<H256, Vec<u8>>,
+}
+
+impl DumpAccount {
+    /// Trie of both kinds of storage. Word slots live under `keccak(key)` and
+    /// byte slots under `keccak(key || 0x01)`, so the same key can hold both.
+    pub fn storage_trie(&self) -> Trie {
+        let mut trie = Trie::new();
+        for (key, value) in &self.storage {
+            trie.insert(storage_key(key).as_bytes(), rlp::encode(value));
+        }
+        for (key, value) in &self.storage_bytes {
+            if !value.is_empty() {
+                trie.insert(storage_bytes_key(key).as_bytes(), rlp::encode(value));
+            }
+        }
+        trie
+    }
+
+    pub fn storage_root(&self) -> H256 {
+        self.storage_trie().root()
+    }
+
+    pub fn trie_account(&self) -> TrieAccount {
+        TrieAccount {
+            nonce: self.nonce,
+            balance: self.balance,
+            storage_root: self.storage_root(),
+            code_hash: utils::keccak(&self.code),
+        }
+    }
+}
+
+/// Merkle proofs of the accounts and storage slots read by an execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub accounts: BTreeMap<Address, Vec<Vec<u8>>>,
+    pub storage: BTreeMap<Address, BTreeMap<H256, Vec<Vec<u8>>>>,
+    pub storage_bytes: BTreeMap<Address, BTreeMap<H256, Vec<Vec<u8>>>>,
+}
+
+/// Full content of a provider, from which the state root is computed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDump {
+    pub accounts: BTreeMap<Address, DumpAccount>,
+}
+
+impl StateDump {
+    /// State made of `diff` alone. Accounts the diff doesn't update have a
+    /// zero balance and nonce.
+    pub fn from_diff(diff: &StateDiff) -> Self {
+        let mut dump = StateDump::default();
+        dump.apply_diff(diff);
+        dump
+    }
+
+    pub fn apply_diff(&mut self, diff: &StateDiff) {
+        for (addr, code) in &diff.created {
+            self.account_mut(addr).code = code.clone();
+        }
+
+        for (addr, (balance, nonce)) in &diff.updated {
+            let account = self.account_mut(addr);
+            account.balance = *balance;
+            account.nonce = *nonce;
+        }
+
+        for (addr, storage) in &diff.storage {
+            let account = self.account_mut(addr);
+            for (key, value) in storage {
+                match value {
+                    Some(value) => account.storage.insert(*key, *value),
+                    None => account.storage.remove(key),
+                };
+            }
+        }
+
+        for (addr, storage) in &diff.storage_bytes {
+            let account = self.account_mut(addr);
+            for (key, value) in storage {
+                account.storage_bytes.insert(*key, value.clone());
+            }
+        }
+    }
+
+    pub fn state_trie(&self) -> Trie {
+        let mut trie = Trie::new();
+        for (addr, account) in &self.accounts {
+            trie.insert(account_key(addr).as_bytes(), rlp::encode(&account.trie_account()));
+        }
+        trie
+    }
+
+    pub fn state_root(&self) -> H256 {
+        self.state_trie().root()
+    }
+
+    /// Storage root of `address`, or the empty root if the account is missing.
+    pub fn storage_root(&self, address: &Address) -> H256 {
+        match self.accounts.get(address) {
+            Some(account) => account.storage_root(),
+            None => trie::empty_root(),
+        }
+    }
+
+    pub fn prove_account(&self, address: &Address) -> Vec<Vec<u8>> {
+        self.state_trie().prove(account_key(address).as_bytes())
+    }
+
+    /// Proof of the word slot `key` of `address`, against its storage root.
+    pub fn prove_storage(&self, address: &Address, key: &H256) -> Vec<Vec<u8>> {
+        self.prove_storage_key(address, &storage_key(key))
+    }
+
+    /// Proof of the byte slot `key` of `address`, against its storage root.
+    pub fn prove_storage_bytes(&self, address: &Address, key: &H256) -> Vec<Vec<u8>> {
+        self.prove_storage_key(address, &storage_bytes_key(key))
+    }
+
+    /// Proofs of everything `witness` read, for a `VerifyingProvider` to check.
+    pub fn prove_witness(&self, witness: &Witness) -> StateProof {
+        let mut proof = StateProof::default();
+        let addresses = witness
+            .exist
+            .keys()
+            .chain(witness.accounts.keys())
+            .chain(witness.storage.keys())
+            .chain(witness.storage_bytes.keys());
+        for addr in addresses {
+            proof
+                .accounts
+                .entry(*addr)
+                .or_insert_with(|| self.prove_account(addr));
+        }
+
+        for (addr, storage) in &witness.storage {
+            let proofs = proof.storage.entry(*addr).or_default();
+            for key in storage.keys() {
+                proofs.insert(*key, self.prove_storage(addr, key));
+            }
+        }
+
+        for (addr, storage) in &witness.storage_bytes {
+            let proofs = proof.storage_bytes.entry(*addr).or_default();
+            for key in storage.keys() {
+                proofs.insert(*key, self.prove_storage_bytes(addr, key));
+            }
+        }
+
+        proof
+    }
+
+    fn prove_storage_key(&self, address: &Address, key: &H256) -> Vec<Vec<u8>> {
+        match self.accounts.get(address) {
+            Some(account) => account.storage_trie().prove(key.as_bytes()),
+            None => Trie::new().prove(key.as_bytes()),
+        }
+    }
+
+    fn account_mut(&mut self, address: &Address) -> &mut DumpAccount {
+        self.accounts.entry(*address).or_default()
+    }
+}
+
+/// Checks `proof` against `state_root` and returns the account it shows for `address`.
+pub fn verify_account_proof(
+    state_root: &H256,
+    address: &Address,
+    proof: &[Vec<u8>],
+) -> Result<Option<TrieAccount>, Error> {
+    match trie::verify_proof(state_root, account_key(address).as_bytes(), proof)? {
+        Some(value) => decode_value(&value).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Checks `proof` against `storage_root` and returns the word it shows for `key`.
+pub fn verify_storage_proof(
+    storage_root: &H256,
+    key: &H256,
+    proof: &[Vec<u8>],
+) -> Result<Option<H256>, Error> {
+    match trie::verify_proof(storage_root, storage_key(key).as_bytes(), proof)? {
+        Some(value) => decode_value(&value).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Checks `proof` against `storage_root` and returns the bytes it shows for `key`.
+pub fn verify_storage_bytes_proof(
+    storage_root: &H256,
+    key: &H256,
+    proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, Error> {
+    match trie::verify_proof(storage_root, storage_bytes_key(key).as_bytes(), proof)? {
+        Some(value) => decode_value(&value).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn decode_value<T: Decodable>(value: &[u8]) -> Result<T, Error> {
+    rlp::decode(value).map_err(|err| Error::InvalidProof {
+        msg: format!("Malformed value: {}", err),
+    })
+}
+
+fn account_key(address: &Address) -> H256 {
+    utils::keccak(address)
+}
+
+fn storage_key(key: &H256) -> H256 {
+    utils::keccak(key)
+}
+
+fn storage_bytes_key(key: &H256) -> H256 {
+    let mut buffer = [0u8; 33];
+    buffer[..32].copy_from_slice(key.as_bytes());
+    buffer[32] = 0x01;
+    utils::keccak(&buffer[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trie_account_round_trips() {
+        let account = TrieAccount {
+            nonce: U256::from(3),
+            balance: U256::from(1_000_000),
+            storage_root: trie::empty_root(),
+            code_hash: utils::keccak([]),
+        };
+        let encoded = rlp::encode(&account);
+        assert_eq!(rlp::decode::<TrieAccount>(&encoded).unwrap(), account);
+    }
+}
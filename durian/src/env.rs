@@ -1,5 +1,6 @@
+use backtrace;
+use schedule::Schedule;
 use std::cell::RefCell;
-use wasm_cost::WasmCosts;
 use wasmi::{
 	self, memory_units, Error, FuncInstance, FuncRef, MemoryDescriptor, MemoryInstance, MemoryRef,
 	Signature,
@@ -32,6 +33,24 @@ pub mod ids {
 	pub const ELOG_FUNC: usize = 210;
 	pub const CREATE2_FUNC: usize = 220;
 	pub const GASLEFT_FUNC: usize = 230;
+	pub const REVERT_FUNC: usize = 240;
+	pub const ENTER_FRAME_FUNC: usize = 250;
+	pub const LEAVE_FRAME_FUNC: usize = 260;
+	pub const KECCAK256_FUNC: usize = 270;
+	pub const SHA256_FUNC: usize = 280;
+	pub const RIPEMD160_FUNC: usize = 290;
+	pub const ECRECOVER_FUNC: usize = 300;
+	pub const ED25519_VERIFY_FUNC: usize = 310;
+	pub const BALANCE_FUNC: usize = 320;
+	pub const SELF_BALANCE_FUNC: usize = 330;
+	pub const CODE_SIZE_FUNC: usize = 340;
+	pub const CODE_HASH_FUNC: usize = 350;
+	pub const ACCOUNT_EXISTS_FUNC: usize = 360;
+	pub const CHAIN_ID_FUNC: usize = 370;
+	pub const STORAGE_READ_BYTES_FUNC: usize = 400;
+	pub const STORAGE_WRITE_BYTES_FUNC: usize = 410;
+	pub const STORAGE_REMOVE_FUNC: usize = 420;
+	pub const STORAGE_CONTAINS_FUNC: usize = 430;
 
 	pub const PANIC_FUNC: usize = 1000;
 	pub const DEBUG_FUNC: usize = 1010;
@@ -98,6 +117,42 @@ pub mod signatures {
 
 	pub const ELOG: StaticSignature = StaticSignature(&[I32, I32, I32, I32], None);
 
+	pub const REVERT: StaticSignature = StaticSignature(&[I32, I32], None);
+
+	pub const KECCAK256: StaticSignature = StaticSignature(&[I32, I32, I32], None);
+
+	pub const SHA256: StaticSignature = StaticSignature(&[I32, I32, I32], None);
+
+	pub const RIPEMD160: StaticSignature = StaticSignature(&[I32, I32, I32], None);
+
+	pub const ECRECOVER: StaticSignature = StaticSignature(&[I32, I32, I32], Some(I32));
+
+	pub const ED25519_VERIFY: StaticSignature = StaticSignature(&[I32, I32, I32, I32], Some(I32));
+
+	pub const BALANCE: StaticSignature = StaticSignature(&[I32, I32], None);
+
+	pub const SELF_BALANCE: StaticSignature = StaticSignature(&[I32], None);
+
+	pub const CODE_SIZE: StaticSignature = StaticSignature(&[I32], Some(I32));
+
+	pub const CODE_HASH: StaticSignature = StaticSignature(&[I32, I32], None);
+
+	pub const ACCOUNT_EXISTS: StaticSignature = StaticSignature(&[I32], Some(I32));
+
+	pub const CHAIN_ID: StaticSignature = StaticSignature(&[I32], None);
+
+	pub const STORAGE_READ_BYTES: StaticSignature = StaticSignature(&[I32, I32, I32], Some(I32));
+
+	pub const STORAGE_WRITE_BYTES: StaticSignature = StaticSignature(&[I32, I32, I32], None);
+
+	pub const STORAGE_REMOVE: StaticSignature = StaticSignature(&[I32], None);
+
+	pub const STORAGE_CONTAINS: StaticSignature = StaticSignature(&[I32], Some(I32));
+
+	pub const ENTER_FRAME: StaticSignature = StaticSignature(&[I32], None);
+
+	pub const LEAVE_FRAME: StaticSignature = StaticSignature(&[], None);
+
 	impl Into<wasmi::Signature> for StaticSignature {
 		fn into(self) -> wasmi::Signature {
 			wasmi::Signature::new(self.0, self.1)
@@ -119,17 +174,31 @@ pub struct ImportResolver {
 
 	have_create2: bool,
 	have_gasleft: bool,
+	have_balance: bool,
+	have_code_size: bool,
+	have_account_exists: bool,
+	have_revert: bool,
+	have_selfbalance: bool,
+	have_extcodehash: bool,
+	have_chain_id: bool,
 }
 
 impl ImportResolver {
 	/// New import resolver with specifed maximum amount of inital memory (in wasm pages = 64kb)
-	pub fn with_limit(max_memory: u32, schedule: &WasmCosts) -> ImportResolver {
+	pub fn with_limit(max_memory: u32, schedule: &Schedule) -> ImportResolver {
 		ImportResolver {
 			max_memory: max_memory,
 			memory: RefCell::new(None),
 
-			have_create2: schedule.have_create2,
-			have_gasleft: schedule.have_gasleft,
+			have_create2: schedule.wasm().have_create2,
+			have_gasleft: schedule.wasm().have_gasleft,
+			have_balance: schedule.wasm().have_balance,
+			have_code_size: schedule.wasm().have_code_size,
+			have_account_exists: schedule.wasm().have_account_exists,
+			have_revert: schedule.have_revert,
+			have_selfbalance: schedule.have_selfbalance,
+			have_extcodehash: schedule.have_extcodehash,
+			have_chain_id: schedule.have_chain_id,
 		}
 	}
 
@@ -189,6 +258,32 @@ impl wasmi::ModuleImportResolver for ImportResolver {
 			"elog" => host(signatures::ELOG, ids::ELOG_FUNC),
 			"create2" if self.have_create2 => host(signatures::CREATE2, ids::CREATE2_FUNC),
 			"gasleft" if self.have_gasleft => host(signatures::GASLEFT, ids::GASLEFT_FUNC),
+			"revert" if self.have_revert => host(signatures::REVERT, ids::REVERT_FUNC),
+			"keccak256" => host(signatures::KECCAK256, ids::KECCAK256_FUNC),
+			"sha256" => host(signatures::SHA256, ids::SHA256_FUNC),
+			"ripemd160" => host(signatures::RIPEMD160, ids::RIPEMD160_FUNC),
+			"balance" if self.have_balance => host(signatures::BALANCE, ids::BALANCE_FUNC),
+			"self_balance" if self.have_selfbalance => {
+				host(signatures::SELF_BALANCE, ids::SELF_BALANCE_FUNC)
+			}
+			"code_size" if self.have_code_size => host(signatures::CODE_SIZE, ids::CODE_SIZE_FUNC),
+			"code_hash" if self.have_extcodehash => host(signatures::CODE_HASH, ids::CODE_HASH_FUNC),
+			"account_exists" if self.have_account_exists => {
+				host(signatures::ACCOUNT_EXISTS, ids::ACCOUNT_EXISTS_FUNC)
+			}
+			"chain_id" if self.have_chain_id => host(signatures::CHAIN_ID, ids::CHAIN_ID_FUNC),
+			"storage_remove" => host(signatures::STORAGE_REMOVE, ids::STORAGE_REMOVE_FUNC),
+			"storage_contains" => host(signatures::STORAGE_CONTAINS, ids::STORAGE_CONTAINS_FUNC),
+			"storage_read_bytes" => {
+				host(signatures::STORAGE_READ_BYTES, ids::STORAGE_READ_BYTES_FUNC)
+			}
+			"storage_write_bytes" => {
+				host(signatures::STORAGE_WRITE_BYTES, ids::STORAGE_WRITE_BYTES_FUNC)
+			}
+			"ecrecover" => host(signatures::ECRECOVER, ids::ECRECOVER_FUNC),
+			"ed25519_verify" => host(signatures::ED25519_VERIFY, ids::ED25519_VERIFY_FUNC),
+			backtrace::ENTER_FRAME => host(signatures::ENTER_FRAME, ids::ENTER_FRAME_FUNC),
+			backtrace::LEAVE_FRAME => host(signatures::LEAVE_FRAME, ids::LEAVE_FRAME_FUNC),
 			_ => {
 				return Err(wasmi::Error::Instantiation(format!(
 					"Export {} not found",
@@ -228,3 +323,44 @@ impl wasmi::ModuleImportResolver for ImportResolver {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use wasm_cost::WasmCosts;
+	use wasmi::ModuleImportResolver;
+
+	fn resolves(schedule: &Schedule, field_name: &str) -> bool {
+		let signature = Signature::new(&[][..], None);
+		ImportResolver::with_limit(16, schedule)
+			.resolve_func(field_name, &signature)
+			.is_ok()
+	}
+
+	#[test]
+	fn account_queries_are_gated() {
+		let schedule = Schedule {
+			wasm: Some(WasmCosts::default()),
+			..Default::default()
+		};
+		let gated = ["balance", "self_balance", "code_size", "code_hash", "account_exists"];
+		for field_name in gated.iter() {
+			assert!(!resolves(&schedule, field_name), "{} is not gated", field_name);
+		}
+
+		let schedule = Schedule {
+			have_selfbalance: true,
+			have_extcodehash: true,
+			wasm: Some(WasmCosts {
+				have_balance: true,
+				have_code_size: true,
+				have_account_exists: true,
+				..Default::default()
+			}),
+			..Default::default()
+		};
+		for field_name in gated.iter() {
+			assert!(resolves(&schedule, field_name), "{} is not resolved", field_name);
+		}
+	}
+}
@@ -1,9 +1,8 @@
-use primitive_types::H256;
+use primitive_types::{H256, U256};
 use snafu::Snafu;
 use address::Address;
-use wasmi::TrapKind;
 
-#[derive(Debug, Clone, Snafu)]
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
 pub enum Error {
     #[snafu(display("Not supported"))]
     NotSupported,
@@ -26,6 +25,9 @@ pub enum Error {
     #[snafu(display("Return result"))]
     Return,
 
+    #[snafu(display("Execution reverted"))]
+    Revert,
+
     #[snafu(display("Suicide result"))]
     SuicideAbort,
 
@@ -76,11 +78,65 @@ pub enum Error {
 
     #[snafu(display("Invalid storage key: {}", key))]
     InvalidStorageKey { key: H256 },
+
+    #[snafu(display("Witness does not cover {}", what))]
+    MissingWitness { what: String },
+
+    #[snafu(display("{}", msg))]
+    ReplayDiverged { msg: String },
+
+    #[snafu(display("ABI error: {}", msg))]
+    Abi { msg: String },
+
+    #[snafu(display("Malformed transaction: {}", msg))]
+    MalformedTransaction { msg: String },
+
+    #[snafu(display("Invalid transaction signature"))]
+    InvalidSignature,
+
+    #[snafu(display("Invalid chain id: expected {}, got {}", expected, got))]
+    InvalidChainId { expected: U256, got: U256 },
+
+    #[snafu(display("Invalid nonce: expected {}, got {}", expected, got))]
+    InvalidNonce { expected: U256, got: U256 },
+
+    #[snafu(display("Invalid proof: {}", msg))]
+    InvalidProof { msg: String },
 }
 
-impl From<wasmi::Trap> for Error {
-    fn from(trap: wasmi::Trap) -> Self {
-        match *trap.kind() {
+/// Kind of a trap raised by the wasm interpreter itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrapKind {
+    Unreachable,
+    MemoryAccessOutOfBounds,
+    TableAccessOutOfBounds,
+    ElemUninitialized,
+    DivisionByZero,
+    InvalidConversionToInt,
+    UnexpectedSignature,
+    StackOverflow,
+}
+
+impl TrapKind {
+    /// Returns `None` for host traps, which are raised by the runtime instead.
+    pub fn from_wasmi(kind: &wasmi::TrapKind) -> Option<TrapKind> {
+        match *kind {
+            wasmi::TrapKind::Unreachable => Some(TrapKind::Unreachable),
+            wasmi::TrapKind::MemoryAccessOutOfBounds => Some(TrapKind::MemoryAccessOutOfBounds),
+            wasmi::TrapKind::TableAccessOutOfBounds => Some(TrapKind::TableAccessOutOfBounds),
+            wasmi::TrapKind::ElemUninitialized => Some(TrapKind::ElemUninitialized),
+            wasmi::TrapKind::DivisionByZero => Some(TrapKind::DivisionByZero),
+            wasmi::TrapKind::InvalidConversionToInt => Some(TrapKind::InvalidConversionToInt),
+            wasmi::TrapKind::UnexpectedSignature => Some(TrapKind::UnexpectedSignature),
+            wasmi::TrapKind::StackOverflow => Some(TrapKind::StackOverflow),
+            wasmi::TrapKind::Host(_) => None,
+        }
+    }
+}
+
+impl From<TrapKind> for Error {
+    fn from(kind: TrapKind) -> Self {
+        match kind {
             TrapKind::Unreachable => Error::Unreachable,
             TrapKind::MemoryAccessOutOfBounds => Error::MemoryAccessViolation,
             TrapKind::TableAccessOutOfBounds | TrapKind::ElemUninitialized => {
@@ -90,7 +146,15 @@ impl From<wasmi::Trap> for Error {
             TrapKind::InvalidConversionToInt => Error::InvalidConversionToInt,
             TrapKind::UnexpectedSignature => Error::InvalidVirtualCall,
             TrapKind::StackOverflow => Error::StackOverflow,
-            TrapKind::Host(_) => Error::Other{msg: "Host error".to_string()},
+        }
+    }
+}
+
+impl From<wasmi::Trap> for Error {
+    fn from(trap: wasmi::Trap) -> Self {
+        match TrapKind::from_wasmi(trap.kind()) {
+            Some(kind) => Error::from(kind),
+            None => Error::Other{msg: "Host error".to_string()},
         }
     }
 }
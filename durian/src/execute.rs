@@ -1,14 +1,19 @@
+use access::AccessReport;
 use address::Address;
+use backtrace::{Backtrace, Traced};
+use block::BlockEnv;
 use env;
-use error::Error;
+use error::{Error, TrapKind};
 use log_entry::LogEntry;
+use panic_payload::PanicPayload;
 use parser;
 use primitive_types::U256;
 use provider::Provider;
 use runtime::Runtime;
 use schedule::Schedule;
 use state::State;
-use transaction::{Action, Transaction};
+use std::cmp;
+use transaction::{Action, SignedTransaction, Transaction};
 use types::{ActionParams, ActionType};
 use utils;
 use wasm_cost::WasmCosts;
@@ -19,9 +24,254 @@ pub struct ResultData {
 	pub data: Vec<u8>,
 	pub contract: Address,
 	pub logs: Vec<LogEntry>,
+	/// Accounts and storage slots the transaction read or wrote.
+	pub accesses: AccessReport,
+	/// Messages the contract passed to `debug`, collected in dev mode only.
+	pub debug_messages: Vec<DebugMessage>,
 }
 
-pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> Result<ResultData, Error> {
+/// Message a contract passed to the `debug` host function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebugMessage {
+	/// Gas used by the execution when the message was emitted.
+	pub gas_used: U256,
+	pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecuteOptions {
+	/// Collect the messages the contract passes to `debug` into the result.
+	pub dev_mode: bool,
+	/// Record a backtrace of failed executions into their diagnostics, which dev mode
+	/// does as well. Off by default, since it slows every function call down.
+	pub backtrace: bool,
+}
+
+/// Diagnostics collected up to the point a failed execution stopped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+	/// Human readable reason of the failure.
+	pub message: String,
+	/// Logs emitted before the failure. Like all other changes they are discarded.
+	pub logs: Vec<LogEntry>,
+	/// Accounts and storage slots touched before the failure.
+	pub accesses: AccessReport,
+	/// Messages the contract passed to `debug`, collected in dev mode only.
+	pub debug_messages: Vec<DebugMessage>,
+	/// Contract functions which were being executed when it stopped.
+	pub backtrace: Backtrace,
+}
+
+/// How an execution ended.
+///
+/// Failures are charged the gas they used and none of their changes are applied.
+/// All of them but `HostFailure` are the contract's own doing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+	Success {
+		gas_used: U256,
+		result: ResultData,
+	},
+	/// Contract called `revert`. `data` is what it passed along.
+	Revert {
+		gas_used: U256,
+		data: Vec<u8>,
+		diagnostics: Diagnostics,
+	},
+	/// All the gas was used up.
+	OutOfGas {
+		gas_used: U256,
+		diagnostics: Diagnostics,
+	},
+	/// Wasm interpreter trapped, e.g. on `unreachable`.
+	Trap {
+		gas_used: U256,
+		kind: TrapKind,
+		diagnostics: Diagnostics,
+	},
+	/// Contract called `panic`, hit a host function it may not use, or passed a
+	/// host function arguments out of bounds.
+	///
+	/// `payload` holds the message and source location and `raw_payload` the
+	/// bytes they were decoded from, if the contract called `panic` itself.
+	ContractPanic {
+		gas_used: U256,
+		payload: Option<PanicPayload>,
+		raw_payload: Vec<u8>,
+		diagnostics: Diagnostics,
+	},
+	/// A host function failed, e.g. the provider could not serve a read.
+	HostFailure {
+		gas_used: U256,
+		error: Error,
+		diagnostics: Diagnostics,
+	},
+}
+
+impl ExecutionOutcome {
+	pub fn gas_used(&self) -> U256 {
+		match self {
+			ExecutionOutcome::Success { gas_used, .. }
+			| ExecutionOutcome::Revert { gas_used, .. }
+			| ExecutionOutcome::OutOfGas { gas_used, .. }
+			| ExecutionOutcome::Trap { gas_used, .. }
+			| ExecutionOutcome::ContractPanic { gas_used, .. }
+			| ExecutionOutcome::HostFailure { gas_used, .. } => *gas_used,
+		}
+	}
+
+	pub fn is_success(&self) -> bool {
+		matches!(self, ExecutionOutcome::Success { .. })
+	}
+
+	/// Accounts and storage slots the execution touched, whether it failed or not.
+	pub fn accesses(&self) -> &AccessReport {
+		match self {
+			ExecutionOutcome::Success { result, .. } => &result.accesses,
+			ExecutionOutcome::Revert { diagnostics, .. }
+			| ExecutionOutcome::OutOfGas { diagnostics, .. }
+			| ExecutionOutcome::Trap { diagnostics, .. }
+			| ExecutionOutcome::ContractPanic { diagnostics, .. }
+			| ExecutionOutcome::HostFailure { diagnostics, .. } => &diagnostics.accesses,
+		}
+	}
+
+	/// Messages the contract passed to `debug`, whether it failed or not.
+	pub fn debug_messages(&self) -> &[DebugMessage] {
+		match self {
+			ExecutionOutcome::Success { result, .. } => &result.debug_messages,
+			ExecutionOutcome::Revert { diagnostics, .. }
+			| ExecutionOutcome::OutOfGas { diagnostics, .. }
+			| ExecutionOutcome::Trap { diagnostics, .. }
+			| ExecutionOutcome::ContractPanic { diagnostics, .. }
+			| ExecutionOutcome::HostFailure { diagnostics, .. } => &diagnostics.debug_messages,
+		}
+	}
+
+	/// Returns the result of a successful execution, or turns the failure into an error.
+	pub fn into_result(self) -> Result<ResultData, Error> {
+		match self {
+			ExecutionOutcome::Success { result, .. } => Ok(result),
+			ExecutionOutcome::Revert { .. } => Err(Error::Revert),
+			ExecutionOutcome::OutOfGas { .. } => Err(Error::GasLimit),
+			ExecutionOutcome::Trap { kind, .. } => Err(Error::from(kind)),
+			ExecutionOutcome::ContractPanic { diagnostics, .. } => Err(Error::Panic {
+				msg: diagnostics.message,
+			}),
+			ExecutionOutcome::HostFailure { error, .. } => Err(error),
+		}
+	}
+}
+
+/// Why the contract stopped running.
+enum Stop {
+	/// Contract returned, or ran to the end of `call`.
+	Finished,
+	/// Wasm interpreter trapped.
+	Trapped(TrapKind),
+	/// Runtime aborted the execution with an error.
+	Aborted(Error),
+}
+
+impl Stop {
+	fn from_trap(trap: &wasmi::Trap) -> Stop {
+		match *trap.kind() {
+			wasmi::TrapKind::Host(ref boxed) => {
+				let runtime_err = boxed
+					.downcast_ref::<Error>()
+					.expect("Host errors other than runtime::Error never produced; qed");
+
+				match *runtime_err {
+					Error::Suicide => {
+						debug!("Contract suicided.");
+						Stop::Finished
+					}
+					Error::Return => {
+						debug!("Contract returned.");
+						Stop::Finished
+					}
+					ref err => Stop::Aborted(err.clone()),
+				}
+			}
+			ref kind => Stop::Trapped(
+				TrapKind::from_wasmi(kind).expect("Host traps are handled above; qed"),
+			),
+		}
+	}
+}
+
+fn diagnostics(runtime: &Runtime, traced: &Traced, message: String) -> Diagnostics {
+	Diagnostics {
+		message,
+		logs: runtime.logs().to_vec(),
+		accesses: runtime.access_report(),
+		debug_messages: runtime.debug_messages().to_vec(),
+		backtrace: traced.backtrace(runtime.call_stack()),
+	}
+}
+
+/// Executes the transaction against `provider`, in the block described by `env`.
+///
+/// Failures of the contract are reported through `ExecutionOutcome`. An error is
+/// only returned if the transaction could not be executed at all, e.g. its code
+/// is not a valid module, or if its changes could not be written to the provider.
+pub fn execute(
+	provider: &mut dyn Provider,
+	env: &BlockEnv,
+	transaction: &Transaction,
+) -> Result<ExecutionOutcome, Error> {
+	execute_with_options(provider, env, transaction, &ExecuteOptions::default())
+}
+
+/// Executes a signed transaction against `provider`, after checking it.
+///
+/// The signature must be from the sender, the chain id must be the one of `env`
+/// and the nonce must be the sender's current nonce. The nonce is then used up
+/// whatever the outcome, failures included, so the transaction can't be replayed.
+pub fn execute_signed(
+	provider: &mut dyn Provider,
+	env: &BlockEnv,
+	signed: &SignedTransaction,
+) -> Result<ExecutionOutcome, Error> {
+	let transaction = signed.verify()?;
+
+	if signed.chain_id != env.chain_id {
+		return Err(Error::InvalidChainId {
+			expected: env.chain_id,
+			got: signed.chain_id,
+		});
+	}
+
+	// Accounts the provider doesn't know have not sent anything yet.
+	let nonce = provider
+		.account(&transaction.sender)
+		.map(|acc| acc.nonce)
+		.unwrap_or_default();
+	if signed.nonce != nonce {
+		return Err(Error::InvalidNonce {
+			expected: nonce,
+			got: signed.nonce,
+		});
+	}
+
+	let outcome = execute(provider, env, transaction)?;
+
+	// The changes of a failed execution are discarded, so the nonce is written separately.
+	let mut state = State::new(provider, env);
+	state.inc_nonce(&transaction.sender)?;
+	state.update_state()?;
+
+	Ok(outcome)
+}
+
+/// Same as `execute`, with `options` applied.
+pub fn execute_with_options(
+	provider: &mut dyn Provider,
+	env: &BlockEnv,
+	transaction: &Transaction,
+	options: &ExecuteOptions,
+) -> Result<ExecutionOutcome, Error> {
+	let mut state = State::new(provider, env);
 	let params = match &transaction.action {
 		Action::Create(code, salt) => {
 			let new_address = utils::contract_address(&transaction.sender, &code, &salt);
@@ -42,8 +292,8 @@ pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> Result
 			}
 		}
 		Action::Call(address) => {
-			let acc = provider.account(&address)?;
-			let code = acc.code.clone();
+			// Read through `State`, so the callee shows up in the access report.
+			let code = state.code(address)?.to_vec();
 			ActionParams {
 				code_address: address.clone(),
 				address: address.clone(),
@@ -61,13 +311,24 @@ pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> Result
 		}
 	};
 
-	let mut schedule = Schedule::default();
-	let wasm = WasmCosts::default();
-	schedule.wasm = Some(wasm);
+	let schedule = Schedule {
+		wasm: Some(WasmCosts {
+			have_balance: true,
+			have_code_size: true,
+			have_account_exists: true,
+			..Default::default()
+		}),
+		have_revert: true,
+		have_chain_id: true,
+		have_selfbalance: true,
+		have_extcodehash: true,
+		..Default::default()
+	};
 
-	let module = parser::payload(&params, schedule.wasm())?;
+	let trace = options.dev_mode || options.backtrace;
+	let (module, traced) = parser::payload(&params, schedule.wasm(), trace)?;
 	let loaded_module = wasmi::Module::from_parity_wasm_module(module)?;
-	let instantiation_resolver = env::ImportResolver::with_limit(16, schedule.wasm());
+	let instantiation_resolver = env::ImportResolver::with_limit(16, &schedule);
 	let module_instance = wasmi::ModuleInstance::new(
 		&loaded_module,
 		&wasmi::ImportsBuilder::new().with_resolver("env", &instantiation_resolver),
@@ -86,7 +347,6 @@ pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> Result
 	let initial_memory = instantiation_resolver.memory_size()?;
 	trace!(target: "wasm", "Contract requested {:?} pages of initial memory", initial_memory);
 
-	let mut state = State::new(provider);
 	let mut runtime = Runtime::new(
 		&params,
 		&schedule,
@@ -95,6 +355,9 @@ pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> Result
 		// cannot overflow, checked above
 		adjusted_gas.low_u64(),
 	);
+	if options.dev_mode {
+		runtime.collect_debug_messages();
+	}
 
 	// cannot overflow if static_region < 2^16,
 	// initial_memory ∈ [0..2^32)
@@ -102,65 +365,217 @@ pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> Result
 	// total_charge ∈ [0..2^64) if static_region ∈ [0..2^16)
 	// qed
 	assert!(runtime.schedule().wasm().initial_mem < 1 << 16);
-	runtime.charge(|s| initial_memory as u64 * s.wasm().initial_mem as u64)?;
-
-	let instance = module_instance.run_start(&mut runtime)?;
-	let invoke_result = instance.invoke_export("call", &[], &mut runtime);
-
-	if let Err(wasmi::Error::Trap(ref trap)) = invoke_result {
-		if let wasmi::TrapKind::Host(ref boxed) = *trap.kind() {
-			let ref runtime_err = boxed
-				.downcast_ref::<Error>()
-				.expect("Host errors other than runtime::Error never produced; qed");
+	let stop = match runtime.charge(|s| initial_memory as u64 * s.wasm().initial_mem as u64) {
+		Err(err) => Stop::Aborted(err),
+		Ok(()) => {
+			let invoke_result = module_instance
+				.run_start(&mut runtime)
+				.map_err(wasmi::Error::Trap)
+				.and_then(|instance| instance.invoke_export("call", &[], &mut runtime));
 
-			let mut have_error = false;
-			match **runtime_err {
-				Error::Suicide => {
-					debug!("Contract suicided.");
-				}
-				Error::Return => {
-					debug!("Contract returned.");
-				}
-				_ => {
-					have_error = true;
-				}
-			}
-			if let (true, Err(e)) = (have_error, invoke_result) {
-				trace!(target: "wasm", "Error executing contract: {:?}", e);
-				return Err(Error::from(e));
+			match invoke_result {
+				Ok(_) => Stop::Finished,
+				Err(wasmi::Error::Trap(ref trap)) => Stop::from_trap(trap),
+				Err(err) => return Err(Error::from(err)),
 			}
 		}
-	}
+	};
 
 	let gas_left = runtime
 		.gas_left()
 		.expect("Cannot fail since it was not updated since last charge");
-	let result = runtime.into_result();
 	let gas_left_adj = U256::from(gas_left) * U256::from(schedule.wasm().opcodes_mul)
 		/ U256::from(schedule.wasm().opcodes_div);
+	let gas_used = params.gas - gas_left_adj;
 
-	if result.is_empty() {
-		trace!(target: "wasm", "Contract execution result is empty.");
-		Ok(ResultData {
-			gas_left: gas_left_adj,
-			data: vec![],
-			contract: params.address,
-			// TODO::::: logs????
-			logs: vec![], // ext.logs().to_vec(),
-		})
-	} else {
-		if let Action::Create(_, _) = &transaction.action {
-			runtime.init_code(&params.address, result.to_vec());
+	let outcome = match stop {
+		Stop::Finished => {
+			let result = runtime.into_result();
+			if result.is_empty() {
+				trace!(target: "wasm", "Contract execution result is empty.");
+			} else if let Action::Create(_, _) = &transaction.action {
+				runtime.init_code(&params.address, result.to_vec());
+			}
+
+			runtime.update_state()?;
+
+			// Storage refunds are only paid on success, and cover at most half of the gas used.
+			let refund = cmp::min(U256::from(runtime.sstore_refund()), gas_used / 2);
+
+			ExecutionOutcome::Success {
+				gas_used: gas_used - refund,
+				result: ResultData {
+					gas_left: gas_left_adj + refund,
+					data: result.to_vec(),
+					contract: params.address,
+					logs: runtime.logs().to_vec(),
+					accesses: runtime.access_report(),
+					debug_messages: runtime.debug_messages().to_vec(),
+				},
+			}
+		}
+		Stop::Trapped(kind) => {
+			let mut diagnostics = diagnostics(&runtime, &traced, format!("{}", Error::from(kind)));
+			if !diagnostics.backtrace.is_empty() {
+				diagnostics.message = format!("{}\n{}", diagnostics.message, diagnostics.backtrace);
+			}
+			ExecutionOutcome::Trap {
+				gas_used,
+				kind,
+				diagnostics,
+			}
+		}
+		Stop::Aborted(Error::Revert) => ExecutionOutcome::Revert {
+			gas_used,
+			data: runtime.into_result(),
+			diagnostics: diagnostics(&runtime, &traced, format!("{}", Error::Revert)),
+		},
+		Stop::Aborted(Error::GasLimit) => ExecutionOutcome::OutOfGas {
+			gas_used: params.gas,
+			diagnostics: diagnostics(&runtime, &traced, format!("{}", Error::GasLimit)),
+		},
+		Stop::Aborted(err @ Error::Panic { .. }) => {
+			let (payload, raw_payload) = match runtime.panic_payload() {
+				Some((payload, raw)) => (Some(payload.clone()), raw.clone()),
+				None => (None, Vec::new()),
+			};
+			ExecutionOutcome::ContractPanic {
+				gas_used,
+				payload,
+				raw_payload,
+				diagnostics: diagnostics(&runtime, &traced, format!("{}", err)),
+			}
 		}
+		Stop::Aborted(err @ Error::MemoryAccessViolation)
+		| Stop::Aborted(err @ Error::BadUtf8)
+		| Stop::Aborted(err @ Error::Log) => ExecutionOutcome::ContractPanic {
+			gas_used,
+			payload: None,
+			raw_payload: Vec::new(),
+			diagnostics: diagnostics(&runtime, &traced, format!("{}", err)),
+		},
+		Stop::Aborted(error) => ExecutionOutcome::HostFailure {
+			gas_used,
+			diagnostics: diagnostics(&runtime, &traced, format!("{}", error)),
+			error,
+		},
+	};
 
-		runtime.update_state()?;
+	if !outcome.is_success() {
+		trace!(target: "wasm", "Error executing contract: {:?}", outcome);
+	}
+
+	Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitive_types::H256;
+	use provider::Provider;
+	use testing::{self, MemoryProvider};
+
+	fn run(code: &str) -> (MemoryProvider, ExecutionOutcome) {
+		let contract = Address::repeat_byte(1);
+		let mut provider = MemoryProvider::default();
+		provider.deploy(contract, code);
+		let outcome = testing::execute(&mut provider, &testing::call(contract, vec![]));
+		(provider, outcome)
+	}
+
+	#[test]
+	fn empty_result_still_updates_state() {
+		let (provider, outcome) = run(r#"
+			(module
+				(import "env" "memory" (memory 1 16))
+				(import "env" "storage_write" (func $storage_write (param i32 i32)))
+				(data (i32.const 32) "\07")
+				(func (export "call")
+					(call $storage_write (i32.const 0) (i32.const 32))))
+		"#);
+
+		assert!(outcome.into_result().unwrap().data.is_empty());
+		let value = provider.storage_at(&Address::repeat_byte(1), &H256::zero());
+		assert_eq!(value.unwrap().as_bytes()[0], 7);
+	}
+
+	#[test]
+	fn host_memory_access_out_of_bounds_is_contract_failure() {
+		let (_, outcome) = run(r#"
+			(module
+				(import "env" "memory" (memory 1 16))
+				(import "env" "storage_read" (func $storage_read (param i32 i32)))
+				(func (export "call")
+					(call $storage_read (i32.const 0) (i32.const 65530))))
+		"#);
+
+		match outcome {
+			ExecutionOutcome::ContractPanic { payload: None, diagnostics, .. } => {
+				assert_eq!(diagnostics.message, format!("{}", Error::MemoryAccessViolation))
+			}
+			outcome => panic!("Expected a contract failure, got {:?}", outcome),
+		}
+	}
+
+	#[test]
+	fn self_balance_is_charged() {
+		let gas_used = |calls: usize| {
+			let call = "(call $self_balance (i32.const 0))";
+			let code = format!(
+				r#"
+				(module
+					(import "env" "memory" (memory 1 16))
+					(import "env" "self_balance" (func $self_balance (param i32)))
+					(func (export "call") {}))
+			"#,
+				vec![call; calls].join(" ")
+			);
+			run(&code).1.gas_used()
+		};
+
+		let schedule = Schedule::default();
+		assert!(gas_used(2) - gas_used(1) >= U256::from(schedule.selfbalance_gas));
+	}
+
+	#[test]
+	fn signed_transactions_use_up_their_nonce() {
+		let contract = Address::repeat_byte(1);
+		let mut provider = MemoryProvider::default();
+		provider.deploy(
+			contract,
+			r#"
+			(module
+				(import "env" "memory" (memory 1 16))
+				(import "env" "revert" (func $revert (param i32 i32)))
+				(func (export "call") (call $revert (i32.const 0) (i32.const 0))))
+		"#,
+		);
+
+		let env = BlockEnv::default();
+		let secret = H256::repeat_byte(0x11);
+		let transaction = testing::call(contract, vec![]);
+		let signed = SignedTransaction::sign(transaction, U256::zero(), env.chain_id, &secret).unwrap();
+		let sender = signed.transaction.sender;
+
+		// Reverted, yet the nonce is used up.
+		match execute_signed(&mut provider, &env, &signed) {
+			Ok(ExecutionOutcome::Revert { .. }) => {}
+			outcome => panic!("Expected a revert, got {:?}", outcome),
+		}
+		assert_eq!(provider.account(&sender).unwrap().nonce, U256::one());
+
+		match execute_signed(&mut provider, &env, &signed) {
+			Err(Error::InvalidNonce { expected, got }) => {
+				assert_eq!((expected, got), (U256::one(), U256::zero()))
+			}
+			outcome => panic!("Expected the replay to be rejected, got {:?}", outcome),
+		}
 
-		Ok(ResultData {
-			gas_left: gas_left_adj,
-			data: result.to_vec(),
-			contract: params.address,
-			// TODO::::: logs????
-			logs: vec![], // ext.logs().to_vec(),
-		})
+		let succeeding = Address::repeat_byte(2);
+		provider.deploy(succeeding, r#"(module (func (export "call")))"#);
+		let transaction = testing::call(succeeding, vec![]);
+		let signed = SignedTransaction::sign(transaction, U256::one(), env.chain_id, &secret).unwrap();
+		assert!(execute_signed(&mut provider, &env, &signed).unwrap().is_success());
+		assert_eq!(provider.account(&sender).unwrap().nonce, U256::from(2));
 	}
 }
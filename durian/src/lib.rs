@@ -1,5 +1,6 @@
 #[macro_use]
 extern crate byteorder;
+extern crate ed25519_dalek;
 #[macro_use]
 extern crate log;
 
@@ -7,22 +8,46 @@ extern crate keccak_hash;
 extern crate parity_wasm;
 extern crate primitive_types;
 extern crate pwasm_utils;
+extern crate ripemd160;
+extern crate rlp;
+extern crate secp256k1;
+#[macro_use]
+extern crate serde;
+extern crate serde_json;
+extern crate sha2;
 extern crate snafu;
 extern crate wasmi;
+#[cfg(test)]
+extern crate wat;
 
+pub mod abi;
+pub mod access;
 pub mod address;
+pub mod backtrace;
+pub mod batch;
+pub mod block;
+pub mod bloom;
+pub mod dump;
 pub mod error;
 pub mod execute;
 pub mod log_entry;
+pub mod overlay;
+pub mod panic_payload;
 pub mod provider;
+pub mod receipt;
+pub mod record;
 pub mod transaction;
+pub mod trie;
+pub mod verifying;
+pub mod witness;
 
 mod env;
-mod panic_payload;
 mod parser;
 mod runtime;
 mod schedule;
 mod state;
+#[cfg(test)]
+mod testing;
 mod types;
 mod utils;
 mod wasm_cost;
@@ -1,7 +1,7 @@
 use primitive_types::H256;
 use address::Address;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LogEntry {
     pub address: Address,
     pub topics: Vec<H256>,
@@ -0,0 +1,177 @@
+use address::Address;
+use error::Error;
+use primitive_types::{H256, U256};
+use provider::{Provider, StateAccount};
+use std::collections::BTreeMap;
+
+/// Changes made on top of a provider, without touching the provider itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiff {
+    /// Code of the contracts created.
+    pub created: BTreeMap<Address, Vec<u8>>,
+    /// New balance and nonce of the accounts updated.
+    pub updated: BTreeMap<Address, (U256, U256)>,
+    /// Storage slots written. `None` marks a removed slot.
+    pub storage: BTreeMap<Address, BTreeMap<H256, Option<H256>>>,
+    pub storage_bytes: BTreeMap<Address, BTreeMap<H256, Vec<u8>>>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty()
+            && self.storage.is_empty()
+            && self.storage_bytes.is_empty()
+    }
+
+    /// Applies `other` on top of this diff. Entries of `other` win.
+    pub fn merge(&mut self, other: StateDiff) {
+        self.created.extend(other.created);
+        self.updated.extend(other.updated);
+        for (addr, storage) in other.storage {
+            self.storage
+                .entry(addr)
+                .or_default()
+                .extend(storage);
+        }
+        for (addr, storage) in other.storage_bytes {
+            self.storage_bytes
+                .entry(addr)
+                .or_default()
+                .extend(storage);
+        }
+    }
+
+    /// Writes the diff to `provider`.
+    pub fn apply(&self, provider: &mut dyn Provider) -> Result<(), Error> {
+        for (addr, code) in &self.created {
+            provider.create_contract(addr, code)?;
+        }
+
+        for (addr, (balance, nonce)) in &self.updated {
+            provider.update_account(addr, balance, nonce)?;
+        }
+
+        for (addr, storage) in &self.storage {
+            for (key, value) in storage {
+                match value {
+                    Some(value) => provider.set_storage(addr, key, value)?,
+                    None => provider.remove_storage(addr, key)?,
+                }
+            }
+        }
+
+        for (addr, storage) in &self.storage_bytes {
+            for (key, value) in storage {
+                provider.set_storage_bytes(addr, key, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Provider which reads through to `base` and keeps all writes in a `StateDiff`.
+pub struct Overlay<'a> {
+    base: &'a dyn Provider,
+    diff: StateDiff,
+}
+
+impl<'a> Overlay<'a> {
+    pub fn new(base: &'a dyn Provider) -> Self {
+        Overlay::with_diff(base, StateDiff::default())
+    }
+
+    pub fn with_diff(base: &'a dyn Provider, diff: StateDiff) -> Self {
+        Overlay { base, diff }
+    }
+
+    pub fn diff(&self) -> &StateDiff {
+        &self.diff
+    }
+
+    pub fn into_diff(self) -> StateDiff {
+        self.diff
+    }
+}
+
+impl<'a> Provider for Overlay<'a> {
+    fn exist(&self, address: &Address) -> bool {
+        self.diff.created.contains_key(address)
+            || self.diff.updated.contains_key(address)
+            || self.base.exist(address)
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        let mut acc = match self.diff.created.get(address) {
+            Some(code) => StateAccount {
+                nonce: U256::zero(),
+                balance: U256::zero(),
+                code: code.clone(),
+            },
+            None => self.base.account(address)?,
+        };
+
+        if let Some((balance, nonce)) = self.diff.updated.get(address) {
+            acc.balance = *balance;
+            acc.nonce = *nonce;
+        }
+
+        Ok(acc)
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        self.diff.updated.insert(*address, (*bal, *nonce));
+        Ok(())
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        self.diff.created.insert(*address, code.clone());
+        Ok(())
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        match self.diff.storage.get(address).and_then(|s| s.get(key)) {
+            Some(Some(value)) => Ok(*value),
+            Some(None) => Err(Error::InvalidStorageKey { key: *key }),
+            None => self.base.storage_at(address, key),
+        }
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        self.diff
+            .storage
+            .entry(*address)
+            .or_default()
+            .insert(*key, Some(*value));
+        Ok(())
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        self.diff
+            .storage
+            .entry(*address)
+            .or_default()
+            .insert(*key, None);
+        Ok(())
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        match self.diff.storage_bytes.get(address).and_then(|s| s.get(key)) {
+            Some(value) => Ok(value.clone()),
+            None => self.base.storage_bytes_at(address, key),
+        }
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        self.diff
+            .storage_bytes
+            .entry(*address)
+            .or_default()
+            .insert(*key, value.to_vec());
+        Ok(())
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        self.base.block_hash(block_no)
+    }
+}
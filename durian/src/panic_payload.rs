@@ -1,7 +1,9 @@
 use byteorder::{LittleEndian, ReadBytesExt};
+use std::fmt;
 use std::io::{self, Read};
 
-#[derive(Debug, PartialEq, Eq)]
+/// Message and source location a contract passed to `panic`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PanicPayload {
 	pub msg: Option<String>,
 	pub file: Option<String>,
@@ -9,6 +11,27 @@ pub struct PanicPayload {
 	pub col: Option<u32>,
 }
 
+impl fmt::Display for PanicPayload {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{msg}, {file}:{line}:{col}",
+			msg = self
+				.msg
+				.as_ref()
+				.map(String::as_ref)
+				.unwrap_or("<msg was stripped>"),
+			file = self
+				.file
+				.as_ref()
+				.map(String::as_ref)
+				.unwrap_or("<unknown>"),
+			line = self.line.unwrap_or(0),
+			col = self.col.unwrap_or(0)
+		)
+	}
+}
+
 fn read_string(rdr: &mut io::Cursor<&[u8]>) -> io::Result<Option<String>> {
 	let string_len = rdr.read_u32::<LittleEndian>()?;
 	let string = if string_len == 0 {
@@ -1,3 +1,4 @@
+use backtrace::{self, Traced};
 use error::Error;
 use parity_wasm::elements::{self, Deserialize};
 use pwasm_utils::{self, rules};
@@ -31,8 +32,15 @@ fn gas_rules(wasm_costs: &WasmCosts) -> rules::Set {
 
 /// Splits payload to code and data according to params_type, also
 /// loads the module instance from payload and injects gas counter according
-/// to schedule.
-pub fn payload<'a>(params: &'a ActionParams, wasm_costs: &WasmCosts) -> Result<elements::Module, Error> {
+/// to schedule. Returns the functions traced for backtraces along with the module.
+///
+/// Calls to the frame tracer are only injected if `trace` is set, since they
+/// slow every function call down.
+pub fn payload(
+	params: &ActionParams,
+	wasm_costs: &WasmCosts,
+	trace: bool,
+) -> Result<(elements::Module, Traced), Error> {
 	let mut cursor = ::std::io::Cursor::new(&params.code[..]);
 
 	let deserialized_module =
@@ -51,6 +59,20 @@ pub fn payload<'a>(params: &'a ActionParams, wasm_costs: &WasmCosts) -> Result<e
 		});
 	}
 
+	// The frame tracer functions may only be called by the injected code, or a
+	// contract could forge its own backtrace.
+	if let Some(import) = deserialized_module.import_section().and_then(|is| {
+		is.entries()
+			.iter()
+			.find(|import| backtrace::is_reserved(import.field()))
+	}) {
+		return Err(Error::Wasm {
+			msg: format!("Malformed wasm module: imports reserved function {}", import.field()),
+		});
+	}
+
+	let traced = Traced::new(&deserialized_module);
+
 	let contract_module =
 		pwasm_utils::inject_gas_counter(deserialized_module, &gas_rules(wasm_costs)).map_err(
 			|_| Error::Wasm {
@@ -64,5 +86,11 @@ pub fn payload<'a>(params: &'a ActionParams, wasm_costs: &WasmCosts) -> Result<e
 				msg: format!("Wasm contract error: stack limiter failure"),
 			})?;
 
-	Ok(contract_module)
+	let contract_module = if trace {
+		backtrace::inject_frame_tracer(contract_module, &traced)
+	} else {
+		contract_module
+	};
+
+	Ok((contract_module, traced))
 }
@@ -15,10 +15,8 @@ pub trait Provider {
     fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error>;
     fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error>;
     fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error>;
-    fn timestamp(&self) -> u64;
-    fn block_number(&self) -> u64;
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error>;
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error>;
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error>;
     fn block_hash(&self, block_no: u64) -> Result<H256, Error>;
-    fn block_author(&self) -> Result<Address, Error>;
-    fn difficulty(&self) -> Result<U256, Error>;
-    fn gas_limit(&self) -> Result<U256, Error>;
 }
@@ -0,0 +1,84 @@
+use address::Address;
+use bloom::Bloom;
+use log_entry::LogEntry;
+use primitive_types::{H256, U256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiptStatus {
+    /// Transaction executed and its changes were applied.
+    Succeeded,
+    /// Transaction failed; it was charged but its changes were discarded.
+    Failed,
+    /// Transaction did not fit into the block gas limit and was not executed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub status: ReceiptStatus,
+    pub gas_used: U256,
+    /// Gas used by this and all previous transactions of the block.
+    pub cumulative_gas_used: U256,
+    pub logs: Vec<LogEntry>,
+    /// Address of the contract created by the transaction, if any.
+    pub contract: Option<Address>,
+    /// Bloom of the addresses and topics of `logs`.
+    pub bloom: Bloom,
+}
+
+impl Receipt {
+    pub fn new(
+        status: ReceiptStatus,
+        gas_used: U256,
+        cumulative_gas_used: U256,
+        logs: Vec<LogEntry>,
+        contract: Option<Address>,
+    ) -> Self {
+        Receipt {
+            status,
+            gas_used,
+            cumulative_gas_used,
+            bloom: Bloom::from_logs(&logs),
+            logs,
+            contract,
+        }
+    }
+
+    /// Whether the receipt might hold a log emitted by `address` with all of
+    /// `topics`. If it returns false, the logs don't need to be looked at.
+    pub fn might_contain(&self, address: Option<&Address>, topics: &[H256]) -> bool {
+        address.is_none_or(|address| self.bloom.contains_address(address))
+            && topics.iter().all(|topic| self.bloom.contains_topic(topic))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn bloom_is_required() {
+        let log = LogEntry {
+            address: Address::repeat_byte(1),
+            topics: vec![H256::repeat_byte(2)],
+            data: vec![],
+        };
+        let receipt = Receipt::new(
+            ReceiptStatus::Succeeded,
+            U256::from(21000),
+            U256::from(21000),
+            vec![log.clone()],
+            None,
+        );
+
+        let json = serde_json::to_value(&receipt).unwrap();
+        let decoded: Receipt = serde_json::from_value(json.clone()).unwrap();
+        assert!(decoded.might_contain(Some(&log.address), &log.topics));
+
+        // An empty bloom would hide the logs from every filter.
+        let mut without_bloom = json;
+        without_bloom.as_object_mut().unwrap().remove("bloom");
+        assert!(serde_json::from_value::<Receipt>(without_bloom).is_err());
+    }
+}
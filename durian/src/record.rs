@@ -0,0 +1,513 @@
+//! Record and replay of provider interactions.
+//!
+//! A recording is a JSON lines file. The first line is a `Header` holding the
+//! format version, the block environment and the executed transaction, every
+//! following line is one `Interaction`: a provider call together with the
+//! response it got, in the order the calls were made.
+
+use address::Address;
+use block::BlockEnv;
+use error::Error;
+use primitive_types::{H256, U256};
+use provider::{Provider, StateAccount};
+use serde_json;
+use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
+use transaction::Transaction;
+use witness::{Entry, WitnessAccount};
+
+pub const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u32,
+    pub env: BlockEnv,
+    pub transaction: Transaction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Call {
+    Exist { address: Address },
+    Account { address: Address },
+    UpdateAccount { address: Address, balance: U256, nonce: U256 },
+    CreateContract { address: Address, code: Vec<u8> },
+    StorageAt { address: Address, key: H256 },
+    SetStorage { address: Address, key: H256, value: H256 },
+    RemoveStorage { address: Address, key: H256 },
+    StorageBytesAt { address: Address, key: H256 },
+    SetStorageBytes { address: Address, key: H256, value: Vec<u8> },
+    BlockHash { block_no: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Response {
+    Bool(bool),
+    Account(Entry<WitnessAccount>),
+    Hash(Entry<H256>),
+    Bytes(Entry<Vec<u8>>),
+    Done(Entry<()>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub call: Call,
+    pub response: Response,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub env: BlockEnv,
+    pub transaction: Transaction,
+    pub interactions: Vec<Interaction>,
+}
+
+impl Recording {
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Recording, Error> {
+        let mut lines = reader.lines();
+        let header: Header = match lines.next() {
+            Some(line) => parse_line(&line.map_err(io_error)?)?,
+            None => {
+                return Err(Error::Other {
+                    msg: "Recording is empty".to_string(),
+                })
+            }
+        };
+        if header.version != FORMAT_VERSION {
+            return Err(Error::Other {
+                msg: format!("Unsupported recording version {}", header.version),
+            });
+        }
+
+        let mut interactions = Vec::new();
+        for line in lines {
+            let line = line.map_err(io_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            interactions.push(parse_line(&line)?);
+        }
+
+        Ok(Recording {
+            env: header.env,
+            transaction: header.transaction,
+            interactions,
+        })
+    }
+}
+
+fn parse_line<T: ::serde::de::DeserializeOwned>(line: &str) -> Result<T, Error> {
+    serde_json::from_str(line).map_err(|e| Error::Other {
+        msg: format!("Malformed recording: {}", e),
+    })
+}
+
+fn io_error(err: io::Error) -> Error {
+    Error::Other {
+        msg: format!("Recording I/O error: {}", err),
+    }
+}
+
+/// Provider decorator which writes every call and its response to `writer`.
+///
+/// Each interaction is written and flushed as soon as the call returns, so a
+/// recording stays usable even if the process dies half way.
+pub struct Recorder<'a, W: Write> {
+    provider: &'a mut dyn Provider,
+    writer: RefCell<W>,
+    error: RefCell<Option<io::Error>>,
+}
+
+impl<'a, W: Write> Recorder<'a, W> {
+    pub fn new(
+        provider: &'a mut dyn Provider,
+        mut writer: W,
+        env: &BlockEnv,
+        transaction: &Transaction,
+    ) -> Result<Self, Error> {
+        let header = Header {
+            version: FORMAT_VERSION,
+            env: env.clone(),
+            transaction: transaction.clone(),
+        };
+        write_line(&mut writer, &header).map_err(io_error)?;
+
+        Ok(Recorder {
+            provider,
+            writer: RefCell::new(writer),
+            error: RefCell::new(None),
+        })
+    }
+
+    /// Returns the writer, or the first I/O error hit while recording.
+    pub fn finish(self) -> Result<W, Error> {
+        match self.error.into_inner() {
+            Some(err) => Err(io_error(err)),
+            None => Ok(self.writer.into_inner()),
+        }
+    }
+
+    fn record(&self, call: Call, response: Response) {
+        let interaction = Interaction { call, response };
+        if let Err(err) = write_line(&mut *self.writer.borrow_mut(), &interaction) {
+            self.error.borrow_mut().get_or_insert(err);
+        }
+    }
+}
+
+fn write_line<W: Write, T: ::serde::Serialize>(writer: &mut W, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+impl<'a, W: Write> Provider for Recorder<'a, W> {
+    fn exist(&self, address: &Address) -> bool {
+        let exist = self.provider.exist(address);
+        self.record(Call::Exist { address: *address }, Response::Bool(exist));
+        exist
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        let result = self.provider.account(address);
+        self.record(
+            Call::Account { address: *address },
+            Response::Account(WitnessAccount::entry(&result)),
+        );
+        result
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        let result = self.provider.update_account(address, bal, nonce);
+        self.record(
+            Call::UpdateAccount {
+                address: *address,
+                balance: *bal,
+                nonce: *nonce,
+            },
+            Response::Done(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        let result = self.provider.create_contract(address, code);
+        self.record(
+            Call::CreateContract {
+                address: *address,
+                code: code.clone(),
+            },
+            Response::Done(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        let result = self.provider.storage_at(address, key);
+        self.record(
+            Call::StorageAt {
+                address: *address,
+                key: *key,
+            },
+            Response::Hash(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        let result = self.provider.set_storage(address, key, value);
+        self.record(
+            Call::SetStorage {
+                address: *address,
+                key: *key,
+                value: *value,
+            },
+            Response::Done(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        let result = self.provider.remove_storage(address, key);
+        self.record(
+            Call::RemoveStorage {
+                address: *address,
+                key: *key,
+            },
+            Response::Done(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        let result = self.provider.storage_bytes_at(address, key);
+        self.record(
+            Call::StorageBytesAt {
+                address: *address,
+                key: *key,
+            },
+            Response::Bytes(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        let result = self.provider.set_storage_bytes(address, key, value);
+        self.record(
+            Call::SetStorageBytes {
+                address: *address,
+                key: *key,
+                value: value.to_vec(),
+            },
+            Response::Done(Entry::from_result(&result)),
+        );
+        result
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        let result = self.provider.block_hash(block_no);
+        self.record(Call::BlockHash { block_no }, Response::Hash(Entry::from_result(&result)));
+        result
+    }
+}
+
+/// Provider which answers calls from a `Recording`.
+///
+/// Calls must arrive in exactly the recorded order. The first call which does
+/// not match is a divergence: fallible calls return `Error::ReplayDiverged`,
+/// which aborts the execution, the others fall back to a default value. `finish` reports the divergence, or any recorded
+/// interaction that was never replayed.
+pub struct Replayer {
+    interactions: Vec<Interaction>,
+    cursor: RefCell<usize>,
+    divergence: RefCell<Option<String>>,
+}
+
+impl Replayer {
+    pub fn new(recording: &Recording) -> Self {
+        Replayer {
+            interactions: recording.interactions.clone(),
+            cursor: RefCell::new(0),
+            divergence: RefCell::new(None),
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Error> {
+        if let Some(msg) = self.divergence.into_inner() {
+            return Err(Error::ReplayDiverged { msg });
+        }
+
+        let cursor = self.cursor.into_inner();
+        if cursor < self.interactions.len() {
+            return Err(Error::ReplayDiverged {
+                msg: format!(
+                    "Replay stopped after {} of {} recorded calls",
+                    cursor,
+                    self.interactions.len()
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn next(&self, call: Call) -> Result<Response, Error> {
+        if let Some(msg) = self.divergence.borrow().as_ref() {
+            return Err(Error::ReplayDiverged { msg: msg.clone() });
+        }
+
+        let mut cursor = self.cursor.borrow_mut();
+        let msg = match self.interactions.get(*cursor) {
+            Some(interaction) if interaction.call == call => {
+                *cursor += 1;
+                return Ok(interaction.response.clone());
+            }
+            Some(interaction) => format!(
+                "Replay diverged at call {}: expected {:?}, got {:?}",
+                *cursor, interaction.call, call
+            ),
+            None => format!("Replay diverged at call {}: unexpected {:?}", *cursor, call),
+        };
+
+        *self.divergence.borrow_mut() = Some(msg.clone());
+        Err(Error::ReplayDiverged { msg })
+    }
+
+    fn mismatch(&self, response: Response) -> Error {
+        let msg = format!("Recorded response {:?} has the wrong type", response);
+        self.divergence.borrow_mut().get_or_insert(msg.clone());
+        Error::ReplayDiverged { msg }
+    }
+}
+
+impl Provider for Replayer {
+    fn exist(&self, address: &Address) -> bool {
+        match self.next(Call::Exist { address: *address }) {
+            Ok(Response::Bool(exist)) => exist,
+            Ok(response) => {
+                self.mismatch(response);
+                false
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        match self.next(Call::Account { address: *address })? {
+            Response::Account(entry) => entry.to_result().map(WitnessAccount::into_state_account),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        let call = Call::UpdateAccount {
+            address: *address,
+            balance: *bal,
+            nonce: *nonce,
+        };
+        match self.next(call)? {
+            Response::Done(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        let call = Call::CreateContract {
+            address: *address,
+            code: code.clone(),
+        };
+        match self.next(call)? {
+            Response::Done(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        let call = Call::StorageAt {
+            address: *address,
+            key: *key,
+        };
+        match self.next(call)? {
+            Response::Hash(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        let call = Call::SetStorage {
+            address: *address,
+            key: *key,
+            value: *value,
+        };
+        match self.next(call)? {
+            Response::Done(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        let call = Call::RemoveStorage {
+            address: *address,
+            key: *key,
+        };
+        match self.next(call)? {
+            Response::Done(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        let call = Call::StorageBytesAt {
+            address: *address,
+            key: *key,
+        };
+        match self.next(call)? {
+            Response::Bytes(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        let call = Call::SetStorageBytes {
+            address: *address,
+            key: *key,
+            value: value.to_vec(),
+        };
+        match self.next(call)? {
+            Response::Done(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        match self.next(Call::BlockHash { block_no })? {
+            Response::Hash(entry) => entry.to_result(),
+            response => Err(self.mismatch(response)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use execute::{self, ExecutionOutcome};
+    use testing::{self, MemoryProvider};
+
+    /// Copies storage slot zero to slot one and returns it.
+    const COPY_SLOT: &str = r#"
+        (module
+            (import "env" "memory" (memory 1 16))
+            (import "env" "storage_read" (func $storage_read (param i32 i32)))
+            (import "env" "storage_write" (func $storage_write (param i32 i32)))
+            (import "env" "ret" (func $ret (param i32 i32)))
+            (func (export "call")
+                (call $storage_read (i32.const 0) (i32.const 64))
+                (i32.store8 (i32.const 63) (i32.const 1))
+                (call $storage_write (i32.const 32) (i32.const 64))
+                (call $ret (i32.const 64) (i32.const 32))))
+    "#;
+
+    fn record(transaction: &Transaction) -> (ExecutionOutcome, Recording) {
+        let contract = Address::repeat_byte(1);
+        let mut provider = MemoryProvider::default();
+        provider.deploy(contract, COPY_SLOT);
+        provider
+            .set_storage(&contract, &H256::zero(), &H256::repeat_byte(7))
+            .unwrap();
+
+        let env = BlockEnv::default();
+        let mut recorder = Recorder::new(&mut provider, Vec::new(), &env, transaction).unwrap();
+        let outcome = testing::execute(&mut recorder, transaction);
+        let bytes = recorder.finish().unwrap();
+        (outcome, Recording::read_from(&bytes[..]).unwrap())
+    }
+
+    #[test]
+    fn replays_recorded_execution() {
+        let transaction = testing::call(Address::repeat_byte(1), vec![]);
+        let (recorded, recording) = record(&transaction);
+        assert_eq!(recording.transaction, transaction);
+        let data = recorded.clone().into_result().unwrap().data;
+        assert_eq!(data, H256::repeat_byte(7).as_bytes());
+
+        let mut replayer = Replayer::new(&recording);
+        let replayed = testing::execute(&mut replayer, &recording.transaction);
+        assert_eq!(replayed, recorded);
+        assert!(replayer.finish().is_ok());
+    }
+
+    #[test]
+    fn reports_divergence() {
+        let (_, recording) = record(&testing::call(Address::repeat_byte(1), vec![]));
+
+        let mut replayer = Replayer::new(&recording);
+        let other = testing::call(Address::repeat_byte(2), vec![]);
+        match execute::execute(&mut replayer, &recording.env, &other) {
+            Err(Error::ReplayDiverged { .. }) => {}
+            result => panic!("Replay kept running after diverging: {:?}", result),
+        }
+        match replayer.finish() {
+            Err(Error::ReplayDiverged { .. }) => {}
+            result => panic!("Divergence was not reported: {:?}", result),
+        }
+    }
+}
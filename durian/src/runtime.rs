@@ -1,11 +1,16 @@
+use access::AccessReport;
 use error::{Error};
+use execute::DebugMessage;
 use log_entry::LogEntry;
-use panic_payload;
+use panic_payload::{self, PanicPayload};
 use primitive_types::{H256, U256};
 use schedule::Schedule;
 use state::State;
+use std::cmp;
+use std::collections::BTreeSet;
 use address::Address;
 use types::{ActionParams, ActionType};
+use utils;
 use wasmi::{MemoryRef, RuntimeArgs, RuntimeValue};
 
 pub struct Runtime<'a> {
@@ -17,6 +22,12 @@ pub struct Runtime<'a> {
 	result: Vec<u8>,
 	state: &'a mut State<'a>,
 	logs: Vec<LogEntry>,
+	panic: Option<(PanicPayload, Vec<u8>)>,
+	debug_messages: Option<Vec<DebugMessage>>,
+	call_stack: Vec<u32>,
+	sstore_clears_refund: u64,
+	refunded_storage: BTreeSet<H256>,
+	refunded_storage_bytes: BTreeSet<H256>,
 }
 
 impl<'a> Runtime<'a> {
@@ -37,13 +48,37 @@ impl<'a> Runtime<'a> {
 			state: state,
 			logs: Vec::new(),
 			result: Vec::new(),
+			panic: None,
+			debug_messages: None,
+			call_stack: Vec::new(),
+			sstore_clears_refund: 0,
+			refunded_storage: BTreeSet::new(),
+			refunded_storage_bytes: BTreeSet::new(),
 		}
 	}
 
+	/// Reads `len` bytes of the sandboxed memory at `ptr`
+	///
+	/// Accesses outside the memory are the contract's fault, so they fail with
+	/// `MemoryAccessViolation` instead of a wasm error.
+	fn memory_get(&self, ptr: u32, len: usize) -> Result<Vec<u8>, Error> {
+		self.memory.get(ptr, len).map_err(|_| Error::MemoryAccessViolation)
+	}
+
+	/// Fills `buf` from the sandboxed memory at `ptr`
+	fn memory_get_into(&self, ptr: u32, buf: &mut [u8]) -> Result<(), Error> {
+		self.memory.get_into(ptr, buf).map_err(|_| Error::MemoryAccessViolation)
+	}
+
+	/// Writes `value` to the sandboxed memory at `ptr`
+	fn memory_set(&self, ptr: u32, value: &[u8]) -> Result<(), Error> {
+		self.memory.set(ptr, value).map_err(|_| Error::MemoryAccessViolation)
+	}
+
 	/// Loads 256-bit hash from the specified sandboxed memory pointer
 	fn h256_at(&self, ptr: u32) -> Result<H256, Error> {
 		let mut buf = [0u8; 32];
-		self.memory.get_into(ptr, &mut buf[..])?;
+		self.memory_get_into(ptr, &mut buf[..])?;
 
 		Ok(H256::from_slice(&buf[..]))
 	}
@@ -51,7 +86,7 @@ impl<'a> Runtime<'a> {
 	/// Loads 160-bit hash (Ethereum address) from the specified sandboxed memory pointer
 	fn address_at(&self, ptr: u32) -> Result<Address, Error> {
 		let mut buf = [0u8; 20];
-		self.memory.get_into(ptr, &mut buf[..])?;
+		self.memory_get_into(ptr, &mut buf[..])?;
 
 		Ok(Address::from_slice(&buf[..]))
 	}
@@ -59,7 +94,7 @@ impl<'a> Runtime<'a> {
 	/// Loads 256-bit integer represented with bigendian from the specified sandboxed memory pointer
 	fn u256_at(&self, ptr: u32) -> Result<U256, Error> {
 		let mut buf = [0u8; 32];
-		self.memory.get_into(ptr, &mut buf[..])?;
+		self.memory_get_into(ptr, &mut buf[..])?;
 
 		Ok(U256::from_big_endian(&buf[..]))
 	}
@@ -146,7 +181,7 @@ impl<'a> Runtime<'a> {
 
 		self.adjusted_charge(|schedule| schedule.sload_gas as u64)?;
 
-		self.memory.set(val_ptr as u32, val.as_bytes())?;
+		self.memory_set(val_ptr, val.as_bytes())?;
 
 		Ok(())
 	}
@@ -168,13 +203,94 @@ impl<'a> Runtime<'a> {
 		self.state.set_storage(&self.params.address, &key, &val);
 
 		if former_val != H256::zero() && val == H256::zero() {
-			let sstore_clears_schedule = self.schedule().sstore_refund_gas;
-			self.add_sstore_refund(sstore_clears_schedule);
+			self.refund_storage_clear(key)?;
+		}
+
+		Ok(())
+	}
+
+	/// Remove a storage slot, so it no longer exists in the host state
+	///
+	///	Signature: `storage_remove(key: *const u8)`
+	pub fn storage_remove(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let key = self.h256_at(args.nth_checked(0)?)?;
+
+		let existed = self.state.storage_contains(&self.params.address, &key)?;
+
+		self.adjusted_charge(|schedule| schedule.sstore_reset_gas as u64)?;
+
+		self.state.remove_storage(&self.params.address, &key);
+
+		if existed {
+			self.refund_storage_clear(key)?;
 		}
 
 		Ok(())
 	}
 
+	/// Check whether a storage slot exists, even if it holds zero
+	///
+	///	Signature: `storage_contains(key: *const u8) -> i32`
+	pub fn storage_contains(&mut self, args: RuntimeArgs) -> Result<RuntimeValue, Error> {
+		let key = self.h256_at(args.nth_checked(0)?)?;
+
+		let contains = self.state.storage_contains(&self.params.address, &key)?;
+
+		self.adjusted_charge(|schedule| schedule.sload_gas as u64)?;
+
+		Ok(RuntimeValue::I32(contains as i32))
+	}
+
+	/// Read a variable-length value from the storage to wasm memory
+	///
+	/// Writes at most `dest_len` bytes and returns the full length of the value,
+	/// so the caller can retry with a bigger buffer.
+	///
+	///	Signature: `storage_read_bytes(key: *const u8, dest: *mut u8, dest_len: i32) -> i32`
+	pub fn storage_read_bytes(&mut self, args: RuntimeArgs) -> Result<RuntimeValue, Error> {
+		let key = self.h256_at(args.nth_checked(0)?)?;
+		let dest_ptr: u32 = args.nth_checked(1)?;
+		let dest_len: u32 = args.nth_checked(2)?;
+
+		let val = self.state.storage_bytes_at(&self.params.address, &key)?.to_vec();
+
+		self.adjusted_charge(|schedule| {
+			schedule.sload_gas as u64 + val.len() as u64 * schedule.sload_byte_gas as u64
+		})?;
+
+		let len = cmp::min(val.len(), dest_len as usize);
+		self.memory_set(dest_ptr, &val[..len])?;
+
+		Ok(RuntimeValue::I32(val.len() as i32))
+	}
+
+	/// Write a variable-length value to the storage from wasm memory
+	///
+	///	Signature: `storage_write_bytes(key: *const u8, value: *const u8, value_len: i32)`
+	pub fn storage_write_bytes(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let key = self.h256_at(args.nth_checked(0)?)?;
+		let val_ptr: u32 = args.nth_checked(1)?;
+		let val_len: u32 = args.nth_checked(2)?;
+
+		let val = self.memory_get(val_ptr, val_len as usize)?;
+		let former_empty = self.state.storage_bytes_at(&self.params.address, &key)?.is_empty();
+
+		let byte_gas = |schedule: &Schedule| val.len() as u64 * schedule.sstore_byte_gas as u64;
+		if former_empty && !val.is_empty() {
+			self.adjusted_charge(|schedule| schedule.sstore_set_gas as u64 + byte_gas(schedule))?;
+		} else {
+			self.adjusted_charge(|schedule| schedule.sstore_reset_gas as u64 + byte_gas(schedule))?;
+		}
+
+		if !former_empty && val.is_empty() {
+			self.refund_storage_bytes_clear(key)?;
+		}
+
+		self.state.set_storage_bytes(&self.params.address, &key, val);
+
+		Ok(())
+	}
+
 	/// Return currently used schedule
 	pub fn schedule(&self) -> &Schedule {
 		self.schedule
@@ -191,16 +307,57 @@ impl<'a> Runtime<'a> {
 
 		trace!(target: "wasm", "Contract ret: {} bytes @ {}", len, ptr);
 
-		self.result = self.memory.get(ptr, len as usize)?;
+		self.result = self.memory_get(ptr, len as usize)?;
 
 		Err(Error::Return)
 	}
 
+	/// Reverts the call, discarding all its changes
+	///
+	/// Syscall takes 2 arguments:
+	/// * pointer in sandboxed memory where the revert data is
+	/// * the length of the data
+	pub fn revert(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let ptr: u32 = args.nth_checked(0)?;
+		let len: u32 = args.nth_checked(1)?;
+
+		trace!(target: "wasm", "Contract revert: {} bytes @ {}", len, ptr);
+
+		self.result = self.memory_get(ptr, len as usize)?;
+
+		Err(Error::Revert)
+	}
+
 	/// Destroy the runtime, returning currently recorded result of the execution
 	pub fn into_result(&self) -> Vec<u8> {
 		self.result.clone()
 	}
 
+	/// Decoded and raw payload the contract passed to `panic`, if it called it
+	pub fn panic_payload(&self) -> Option<&(PanicPayload, Vec<u8>)> {
+		self.panic.as_ref()
+	}
+
+	/// Keep the messages the contract passes to `debug` instead of only tracing them
+	pub fn collect_debug_messages(&mut self) {
+		self.debug_messages.get_or_insert_with(Vec::new);
+	}
+
+	/// Debug messages collected so far
+	pub fn debug_messages(&self) -> &[DebugMessage] {
+		self.debug_messages.as_deref().unwrap_or(&[])
+	}
+
+	/// Indices of the contract functions currently being executed, outermost first
+	pub fn call_stack(&self) -> &[u32] {
+		&self.call_stack
+	}
+
+	/// Logs emitted so far
+	pub fn logs(&self) -> &[LogEntry] {
+		&self.logs
+	}
+
 	/// Query current gas left for execution
 	pub fn gas_left(&self) -> Result<u64, Error> {
 		if self.gas_counter > self.gas_limit {
@@ -231,7 +388,7 @@ impl<'a> Runtime<'a> {
 		let args_len = self.params.args.len() as u64;
 		self.charge(|s| args_len * s.wasm().memcpy as u64)?;
 
-		self.memory.set(ptr, &self.params.args[..])?;
+		self.memory_set(ptr, &self.params.args[..])?;
 		Ok(())
 	}
 
@@ -242,24 +399,11 @@ impl<'a> Runtime<'a> {
 		let payload_ptr: u32 = args.nth_checked(0)?;
 		let payload_len: u32 = args.nth_checked(1)?;
 
-		let raw_payload = self.memory.get(payload_ptr, payload_len as usize)?;
+		let raw_payload = self.memory_get(payload_ptr, payload_len as usize)?;
 		let payload = panic_payload::decode(&raw_payload);
-		let msg = format!(
-			"{msg}, {file}:{line}:{col}",
-			msg = payload
-				.msg
-				.as_ref()
-				.map(String::as_ref)
-				.unwrap_or("<msg was stripped>"),
-			file = payload
-				.file
-				.as_ref()
-				.map(String::as_ref)
-				.unwrap_or("<unknown>"),
-			line = payload.line.unwrap_or(0),
-			col = payload.col.unwrap_or(0)
-		);
+		let msg = payload.to_string();
 		trace!(target: "wasm", "Contract custom panic message: {}", msg);
+		self.panic = Some((payload, raw_payload));
 
 		Err(Error::Panic { msg })
 	}
@@ -313,7 +457,7 @@ impl<'a> Runtime<'a> {
 		result.resize(result_alloc_len as usize, 0);
 
 		// todo: optimize to use memory views once it's in
-		let _payload = self.memory.get(input_ptr, input_len as usize)?;
+		let _payload = self.memory_get(input_ptr, input_len as usize)?;
 
 		let adjusted_gas = match gas
 			.checked_mul(self.schedule.wasm().opcodes_div as u64)
@@ -404,7 +548,7 @@ impl<'a> Runtime<'a> {
 
 	fn return_address_ptr(&mut self, ptr: u32, val: Address) -> Result<(), Error> {
 		self.charge(|schedule| schedule.wasm().static_address as u64)?;
-		self.memory.set(ptr, val.as_bytes())?;
+		self.memory_set(ptr, val.as_bytes())?;
 		Ok(())
 	}
 
@@ -412,7 +556,7 @@ impl<'a> Runtime<'a> {
 		let mut ret = H256::zero();
 		val.to_big_endian(ret.as_bytes_mut());
 		self.charge(|schedule| schedule.wasm().static_u256 as u64)?;
-		self.memory.set(ptr, ret.as_bytes())?;
+		self.memory_set(ptr, ret.as_bytes())?;
 		Ok(())
 	}
 
@@ -523,14 +667,124 @@ impl<'a> Runtime<'a> {
 	}
 
 	fn debug(&mut self, args: RuntimeArgs) -> Result<(), Error> {
-		trace!(target: "wasm", "Contract debug message: {}", {
-			let msg_ptr: u32 = args.nth_checked(0)?;
-			let msg_len: u32 = args.nth_checked(1)?;
+		let msg_ptr: u32 = args.nth_checked(0)?;
+		let msg_len: u32 = args.nth_checked(1)?;
 
-			String::from_utf8(self.memory.get(msg_ptr, msg_len as usize)?)
-				.map_err(|_| Error::BadUtf8)?
-		});
+		let message = String::from_utf8(self.memory_get(msg_ptr, msg_len as usize)?)
+			.map_err(|_| Error::BadUtf8)?;
+		trace!(target: "wasm", "Contract debug message: {}", message);
+
+		let gas_used = U256::from(self.gas_counter) * U256::from(self.schedule.wasm().opcodes_mul)
+			/ U256::from(self.schedule.wasm().opcodes_div);
+		if let Some(ref mut messages) = self.debug_messages {
+			messages.push(DebugMessage { gas_used, message });
+		}
+
+		Ok(())
+	}
+
+	/// Charges for hashing the input the args point to, then reads it from memory
+	fn hash_input(&mut self, args: &RuntimeArgs) -> Result<Vec<u8>, Error> {
+		let input_ptr: u32 = args.nth_checked(0)?;
+		let input_len: u32 = args.nth_checked(1)?;
+
+		let words = (input_len as u64).div_ceil(32);
+		self.adjusted_charge(|schedule| {
+			schedule.sha3_gas as u64 + schedule.sha3_word_gas as u64 * words
+		})?;
+
+		self.memory_get(input_ptr, input_len as usize)
+	}
+
+	/// Writes the Keccak-256 digest of the input to the output pointer
+	fn keccak256(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let input = self.hash_input(&args)?;
+		let digest_ptr: u32 = args.nth_checked(2)?;
 
+		self.memory_set(digest_ptr, utils::keccak(&input).as_bytes())?;
+		Ok(())
+	}
+
+	/// Writes the SHA-256 digest of the input to the output pointer
+	fn sha256(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let input = self.hash_input(&args)?;
+		let digest_ptr: u32 = args.nth_checked(2)?;
+
+		self.memory_set(digest_ptr, utils::sha256(&input).as_bytes())?;
+		Ok(())
+	}
+
+	/// Writes the 20 byte RIPEMD-160 digest of the input to the output pointer
+	fn ripemd160(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let input = self.hash_input(&args)?;
+		let digest_ptr: u32 = args.nth_checked(2)?;
+
+		self.memory_set(digest_ptr, utils::ripemd160(&input).as_bytes())?;
+		Ok(())
+	}
+
+	/// Recovers the signer of a secp256k1 signature
+	///
+	/// Reads the 32 byte message hash and the 65 byte `r || s || v` signature and
+	/// writes the signer's address to the output pointer. Returns 0 on success and
+	/// -1 if the signature is invalid, in which case nothing is written.
+	fn ecrecover(&mut self, args: RuntimeArgs) -> Result<RuntimeValue, Error> {
+		let hash_ptr: u32 = args.nth_checked(0)?;
+		let signature_ptr: u32 = args.nth_checked(1)?;
+		let address_ptr: u32 = args.nth_checked(2)?;
+
+		self.charge(|schedule| schedule.wasm().ecrecover as u64)?;
+
+		let hash = self.h256_at(hash_ptr)?;
+		let mut signature = [0u8; 65];
+		self.memory_get_into(signature_ptr, &mut signature[..])?;
+
+		match utils::ecrecover(&hash, &signature) {
+			Some(address) => {
+				self.memory_set(address_ptr, address.as_bytes())?;
+				Ok(RuntimeValue::I32(0))
+			}
+			None => Ok(RuntimeValue::I32(-1)),
+		}
+	}
+
+	/// Verifies an ed25519 signature
+	///
+	/// Reads the message, the 64 byte signature and the 32 byte public key.
+	/// Returns 0 if the signature is valid and -1 otherwise.
+	fn ed25519_verify(&mut self, args: RuntimeArgs) -> Result<RuntimeValue, Error> {
+		let msg_ptr: u32 = args.nth_checked(0)?;
+		let msg_len: u32 = args.nth_checked(1)?;
+		let signature_ptr: u32 = args.nth_checked(2)?;
+		let public_key_ptr: u32 = args.nth_checked(3)?;
+
+		self.charge(|schedule| {
+			schedule.wasm().ed25519_verify as u64 + msg_len as u64 * schedule.wasm().memcpy as u64
+		})?;
+
+		let message = self.memory_get(msg_ptr, msg_len as usize)?;
+		let mut signature = [0u8; 64];
+		self.memory_get_into(signature_ptr, &mut signature[..])?;
+		let mut public_key = [0u8; 32];
+		self.memory_get_into(public_key_ptr, &mut public_key[..])?;
+
+		if utils::ed25519_verify(&message, &signature, &public_key) {
+			Ok(RuntimeValue::I32(0))
+		} else {
+			Ok(RuntimeValue::I32(-1))
+		}
+	}
+
+	/// Function of the contract entered, injected by the frame tracer
+	fn enter_frame(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let index: u32 = args.nth_checked(0)?;
+		self.call_stack.push(index);
+		Ok(())
+	}
+
+	/// Function of the contract about to return, injected by the frame tracer
+	fn leave_frame(&mut self) -> Result<(), Error> {
+		self.call_stack.pop();
 		Ok(())
 	}
 
@@ -567,7 +821,7 @@ impl<'a> Runtime<'a> {
 	pub fn block_hash(&mut self, args: RuntimeArgs) -> Result<(), Error> {
 		self.adjusted_charge(|schedule| schedule.blockhash_gas as u64)?;
 		let hash = self.state.block_hash(args.nth_checked::<u64>(0)?)?;
-		self.memory.set(args.nth_checked(1)?, hash.as_bytes())?;
+		self.memory_set(args.nth_checked(1)?, hash.as_bytes())?;
 
 		Ok(())
 	}
@@ -579,19 +833,19 @@ impl<'a> Runtime<'a> {
 
 	///	Signature: `fn block_author(dest: *mut u8)`
 	pub fn block_author(&mut self, args: RuntimeArgs) -> Result<(), Error> {
-		let author = self.state.block_author()?;
+		let author = self.state.block_author();
 		self.return_address_ptr(args.nth_checked(0)?, author)
 	}
 
 	///	Signature: `fn difficulty(dest: *mut u8)`
 	pub fn difficulty(&mut self, args: RuntimeArgs) -> Result<(), Error> {
-		let difficulty = self.state.difficulty()?;
+		let difficulty = self.state.difficulty();
 		self.return_u256_ptr(args.nth_checked(0)?, difficulty)
 	}
 
 	///	Signature: `fn gaslimit(dest: *mut u8)`
 	pub fn gaslimit(&mut self, args: RuntimeArgs) -> Result<(), Error> {
-		let gas_limit = self.state.gas_limit()?;
+		let gas_limit = self.state.gas_limit();
 		self.return_u256_ptr(args.nth_checked(0)?, gas_limit)
 	}
 
@@ -627,6 +881,59 @@ impl<'a> Runtime<'a> {
 		self.return_address_ptr(args.nth_checked(0)?, origin)
 	}
 
+	///	Signature: `chain_id(dest: *mut u8)`
+	pub fn chain_id(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let chain_id = self.state.chain_id();
+		self.return_u256_ptr(args.nth_checked(0)?, chain_id)
+	}
+
+	///	Signature: `balance(address: *const u8, dest: *mut u8)`
+	pub fn balance(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let address = self.address_at(args.nth_checked(0)?)?;
+		self.adjusted_charge(|schedule| schedule.balance_gas as u64)?;
+		let balance = self.state.balance(&address)?;
+		self.return_u256_ptr(args.nth_checked(1)?, balance)
+	}
+
+	///	Signature: `self_balance(dest: *mut u8)`
+	pub fn self_balance(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		self.adjusted_charge(|schedule| schedule.selfbalance_gas as u64)?;
+		let balance = self.state.balance(&self.params.address)?;
+		self.return_u256_ptr(args.nth_checked(0)?, balance)
+	}
+
+	///	Signature: `code_size(address: *const u8) -> i32`
+	pub fn code_size(&mut self, args: RuntimeArgs) -> Result<RuntimeValue, Error> {
+		let address = self.address_at(args.nth_checked(0)?)?;
+		self.adjusted_charge(|schedule| schedule.extcodesize_gas as u64)?;
+		let size = self.state.code(&address)?.len();
+		Ok(RuntimeValue::I32(size as i32))
+	}
+
+	///	Signature: `code_hash(address: *const u8, dest: *mut u8)`
+	///
+	/// Writes zero for accounts which don't exist.
+	pub fn code_hash(&mut self, args: RuntimeArgs) -> Result<(), Error> {
+		let address = self.address_at(args.nth_checked(0)?)?;
+		let dest_ptr: u32 = args.nth_checked(1)?;
+		self.adjusted_charge(|schedule| schedule.extcodehash_gas as u64)?;
+		let hash = if self.state.exist(&address)? {
+			utils::keccak(self.state.code(&address)?)
+		} else {
+			H256::zero()
+		};
+		self.memory_set(dest_ptr, hash.as_bytes())?;
+		Ok(())
+	}
+
+	///	Signature: `account_exists(address: *const u8) -> i32`
+	pub fn account_exists(&mut self, args: RuntimeArgs) -> Result<RuntimeValue, Error> {
+		let address = self.address_at(args.nth_checked(0)?)?;
+		self.adjusted_charge(|schedule| schedule.account_exists_gas as u64)?;
+		let exists = self.state.exist(&address)?;
+		Ok(RuntimeValue::I32(exists as i32))
+	}
+
 	///	Signature: `fn elog(topic_ptr: *const u8, topic_count: u32, data_ptr: *const u8, data_len: u32)`
 	pub fn elog(&mut self, args: RuntimeArgs) -> Result<(), Error> {
 		let topic_ptr: u32 = args.nth_checked(0)?;
@@ -657,10 +964,10 @@ impl<'a> Runtime<'a> {
 
 			*topics.get_mut(i as usize)
 				.expect("topics is resized to `topic_count`, i is in 0..topic count iterator, get_mut uses i as an indexer, get_mut cannot fail; qed")
-				= H256::from_slice(&self.memory.get(offset, 32)?[..]);
+				= H256::from_slice(&self.memory_get(offset, 32)?[..]);
 		}
 
-		let data = self.memory.get(data_ptr, data_len as usize)?;
+		let data = self.memory_get(data_ptr, data_len as usize)?;
 
 		self.logs.push(LogEntry {
 			address: self.params.address.clone(),
@@ -671,9 +978,37 @@ impl<'a> Runtime<'a> {
 		Ok(())
 	}
 
-	fn add_sstore_refund(&mut self, _value: usize) {
-		// TODO: Better calculate the gas after flushing the state
-		//self.substate.sstore_clears_refund += value as i128;
+	fn add_sstore_refund(&mut self, value: usize) {
+		self.sstore_clears_refund += value as u64;
+	}
+
+	/// Refunds clearing the slot `key`, if it existed before the execution and was
+	/// not refunded yet. Like EIP-2200, slots the execution created itself are not
+	/// refunded, or writing and clearing one in a loop would mint gas.
+	fn refund_storage_clear(&mut self, key: H256) -> Result<(), Error> {
+		if self.state.storage_existed(&self.params.address, &key)?
+			&& self.refunded_storage.insert(key)
+		{
+			let sstore_clears_schedule = self.schedule().sstore_refund_gas;
+			self.add_sstore_refund(sstore_clears_schedule);
+		}
+		Ok(())
+	}
+
+	/// Same as `refund_storage_clear`, for byte slots.
+	fn refund_storage_bytes_clear(&mut self, key: H256) -> Result<(), Error> {
+		if self.state.storage_bytes_existed(&self.params.address, &key)?
+			&& self.refunded_storage_bytes.insert(key)
+		{
+			let sstore_clears_schedule = self.schedule().sstore_refund_gas;
+			self.add_sstore_refund(sstore_clears_schedule);
+		}
+		Ok(())
+	}
+
+	/// Gas refunded for clearing storage, not yet bounded by the gas used
+	pub fn sstore_refund(&self) -> u64 {
+		self.sstore_clears_refund
 	}
 
 	pub fn init_code(&mut self, address: &Address, code: Vec<u8>) {
@@ -683,6 +1018,10 @@ impl<'a> Runtime<'a> {
 	pub fn update_state(&mut self) -> Result<(), Error> {
 		self.state.update_state()
 	}
+
+	pub fn access_report(&self) -> AccessReport {
+		self.state.access_report()
+	}
 }
 
 mod ext_impl {
@@ -711,6 +1050,10 @@ mod ext_impl {
 			match index {
 				STORAGE_WRITE_FUNC => void!(self.storage_write(args)),
 				STORAGE_READ_FUNC => void!(self.storage_read(args)),
+				STORAGE_REMOVE_FUNC => void!(self.storage_remove(args)),
+				STORAGE_CONTAINS_FUNC => some!(self.storage_contains(args)),
+				STORAGE_READ_BYTES_FUNC => some!(self.storage_read_bytes(args)),
+				STORAGE_WRITE_BYTES_FUNC => void!(self.storage_write_bytes(args)),
 				RET_FUNC => void!(self.ret(args)),
 				GAS_FUNC => void!(self.gas(args)),
 				INPUT_LENGTH_FUNC => cast!(self.input_legnth()),
@@ -735,8 +1078,131 @@ mod ext_impl {
 				ELOG_FUNC => void!(self.elog(args)),
 				//CREATE2_FUNC => some!(self.create2(args)),
 				GASLEFT_FUNC => some!(self.gasleft()),
+				REVERT_FUNC => void!(self.revert(args)),
+				KECCAK256_FUNC => void!(self.keccak256(args)),
+				SHA256_FUNC => void!(self.sha256(args)),
+				RIPEMD160_FUNC => void!(self.ripemd160(args)),
+				CHAIN_ID_FUNC => void!(self.chain_id(args)),
+				BALANCE_FUNC => void!(self.balance(args)),
+				SELF_BALANCE_FUNC => void!(self.self_balance(args)),
+				CODE_SIZE_FUNC => some!(self.code_size(args)),
+				CODE_HASH_FUNC => void!(self.code_hash(args)),
+				ACCOUNT_EXISTS_FUNC => some!(self.account_exists(args)),
+				ECRECOVER_FUNC => some!(self.ecrecover(args)),
+				ED25519_VERIFY_FUNC => some!(self.ed25519_verify(args)),
+				ENTER_FRAME_FUNC => void!(self.enter_frame(args)),
+				LEAVE_FRAME_FUNC => void!(self.leave_frame()),
 				_ => panic!("env module doesn't provide function at index {}", index),
 			}
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use primitive_types::{H256, U256};
+	use provider::Provider;
+	use schedule::Schedule;
+	use address::Address;
+	use testing::{self, MemoryProvider};
+
+	/// Gas used by a contract running `body`, with slot 0 holding 7 beforehand if
+	/// `existing` is set. Memory holds the keys 0 at 0 and 1 at 160, and the values
+	/// 7 at 32, 0 at 64 and 8 at 96.
+	fn gas_used(existing: bool, body: &str) -> U256 {
+		let contract = Address::repeat_byte(1);
+		let mut provider = MemoryProvider::default();
+		provider.deploy(
+			contract,
+			&format!(
+				r#"
+				(module
+					(import "env" "memory" (memory 1 16))
+					(import "env" "storage_write" (func $write (param i32 i32)))
+					(import "env" "storage_remove" (func $remove (param i32)))
+					(data (i32.const 32) "\07")
+					(data (i32.const 96) "\08")
+					(data (i32.const 160) "\01")
+					(func (export "call") (local $i i32) {}))
+			"#,
+				body
+			),
+		);
+		if existing {
+			let value = H256::from_slice(&[7; 32]);
+			provider.set_storage(&contract, &H256::zero(), &value).unwrap();
+		}
+
+		let outcome = testing::execute(&mut provider, &testing::call(contract, vec![]));
+		assert!(outcome.is_success(), "{:?}", outcome);
+		outcome.gas_used()
+	}
+
+	#[test]
+	fn creating_and_removing_in_a_loop_is_not_refunded() {
+		let gas_used = gas_used(
+			false,
+			r#"
+			(loop $again
+				(call $write (i32.const 0) (i32.const 32))
+				(call $remove (i32.const 0))
+				(local.set $i (i32.add (local.get $i) (i32.const 1)))
+				(br_if $again (i32.lt_u (local.get $i) (i32.const 10))))
+		"#,
+		);
+
+		// Each iteration sets and removes the slot. Charges lose less than one gas
+		// each when converted to wasm gas and back.
+		let schedule = Schedule::default();
+		let iteration = schedule.sstore_set_gas + schedule.sstore_reset_gas - 2;
+		assert!(gas_used >= U256::from(10 * iteration), "{}", gas_used);
+	}
+
+	#[test]
+	fn clearing_existing_slot_is_refunded() {
+		let cleared = gas_used(true, "(call $write (i32.const 0) (i32.const 64))");
+		let reset = gas_used(true, "(call $write (i32.const 0) (i32.const 96))");
+		assert!(cleared < reset);
+
+		let removed = gas_used(true, "(call $remove (i32.const 0))");
+		assert!(removed < reset);
+	}
+
+	#[test]
+	fn clearing_created_slot_is_not_refunded() {
+		let cleared = gas_used(
+			false,
+			"(call $write (i32.const 0) (i32.const 32)) (call $write (i32.const 0) (i32.const 64))",
+		);
+		let reset = gas_used(
+			false,
+			"(call $write (i32.const 0) (i32.const 32)) (call $write (i32.const 0) (i32.const 96))",
+		);
+		assert_eq!(cleared, reset);
+	}
+
+	#[test]
+	fn clearing_is_refunded_once_per_slot() {
+		// Writing slot 1 raises the gas used, so a second refund would not be
+		// hidden by the refund being capped to half of it.
+		let cleared_twice = gas_used(
+			true,
+			r#"
+			(call $write (i32.const 160) (i32.const 32))
+			(call $write (i32.const 0) (i32.const 64))
+			(call $write (i32.const 0) (i32.const 32))
+			(call $write (i32.const 0) (i32.const 64))
+		"#,
+		);
+		let cleared_once = gas_used(
+			true,
+			r#"
+			(call $write (i32.const 160) (i32.const 32))
+			(call $write (i32.const 0) (i32.const 64))
+			(call $write (i32.const 0) (i32.const 32))
+			(call $write (i32.const 0) (i32.const 96))
+		"#,
+		);
+		assert_eq!(cleared_twice, cleared_once);
+	}
+}
@@ -32,6 +32,8 @@ pub struct Schedule {
 	pub sha3_word_gas: usize,
 	/// Gas price for loading from storage
 	pub sload_gas: usize,
+	/// Additional gas for each byte of a variable-length value loaded from storage
+	pub sload_byte_gas: usize,
 	/// Special gas price for dirty gas of SSTORE, after net gas metering.
 	pub sstore_dirty_gas: Option<usize>,
 	/// Gas price for setting new value to storage (`storage==0`, `new!=0`)
@@ -40,6 +42,8 @@ pub struct Schedule {
 	pub sstore_reset_gas: usize,
 	/// Gas refund for `SSTORE` clearing (when `storage!=0`, `new==0`)
 	pub sstore_refund_gas: usize,
+	/// Additional gas for each byte of a variable-length value written to storage
+	pub sstore_byte_gas: usize,
 	/// Gas price for `JUMPDEST` opcode
 	pub jumpdest_gas: usize,
 	/// Gas price for `LOG*`
@@ -84,6 +88,10 @@ pub struct Schedule {
 	pub extcodecopy_base_gas: usize,
 	/// Price of BALANCE
 	pub balance_gas: usize,
+	/// Price of SELFBALANCE
+	pub selfbalance_gas: usize,
+	/// Price of checking whether an account exists
+	pub account_exists_gas: usize,
 	/// Price of EXTCODEHASH
 	pub extcodehash_gas: usize,
 	/// Price of SUICIDE
@@ -158,10 +166,12 @@ impl Schedule {
 			sha3_gas: 30,
 			sha3_word_gas: 6,
 			sload_gas: 50,
+			sload_byte_gas: 25,
 			sstore_dirty_gas: None,
 			sstore_set_gas: 20000,
 			sstore_reset_gas: 5000,
 			sstore_refund_gas: 15000,
+			sstore_byte_gas: 625,
 			jumpdest_gas: 1,
 			log_gas: 375,
 			log_data_gas: 8,
@@ -185,6 +195,8 @@ impl Schedule {
 			extcodecopy_base_gas: 20,
 			extcodehash_gas: 400,
 			balance_gas: 20,
+			selfbalance_gas: 5,
+			account_exists_gas: 20,
 			suicide_gas: 0,
 			suicide_to_new_account_cost: 0,
 			sub_gas_cap_divisor: None,
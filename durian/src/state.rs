@@ -1,8 +1,10 @@
+use access::{Access, AccessReport};
+use block::BlockEnv;
 use error::Error;
 use log::debug;
 use primitive_types::{H256, U256};
 use provider::Provider;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use address::Address;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,7 +12,13 @@ struct AccountInfo {
     nonce: U256,
     balance: U256,
     code: Vec<u8>,
-    storage: HashMap<H256, (H256, bool)>,
+    /// Storage slots by key. `None` marks a slot which doesn't exist or was removed.
+    storage: BTreeMap<H256, (Option<H256>, bool)>,
+    storage_bytes: BTreeMap<H256, (Vec<u8>, bool)>,
+    /// Keys of the fetched slots which existed before the execution.
+    original_storage: BTreeSet<H256>,
+    /// Keys of the fetched byte slots which were non-empty before the execution.
+    original_storage_bytes: BTreeSet<H256>,
 }
 
 impl AccountInfo {
@@ -19,21 +27,26 @@ impl AccountInfo {
             nonce,
             balance,
             code,
-            storage: HashMap::new(),
+            storage: BTreeMap::new(),
+            storage_bytes: BTreeMap::new(),
+            original_storage: BTreeSet::new(),
+            original_storage_bytes: BTreeSet::new(),
         }
     }
 }
 
 pub struct State<'a> {
     provider: &'a mut dyn Provider,
-    accounts: HashMap<Address, (AccountInfo, bool)>,
+    env: &'a BlockEnv,
+    accounts: BTreeMap<Address, (AccountInfo, bool)>,
 }
 
 impl<'a> State<'a> {
-    pub fn new(provider: &'a mut dyn Provider) -> Self {
+    pub fn new(provider: &'a mut dyn Provider, env: &'a BlockEnv) -> Self {
         State {
-            provider: provider,
-            accounts: HashMap::new(),
+            provider,
+            env,
+            accounts: BTreeMap::new(),
         }
     }
 
@@ -43,38 +56,61 @@ impl<'a> State<'a> {
         Ok(acc.nonce)
     }
 
+    /// Uses up the current nonce of the account.
+    pub fn inc_nonce(&mut self, address: &Address) -> Result<(), Error> {
+        let acc = self.account_mut(address)?;
+        acc.0.nonce += U256::one();
+        acc.1 = true;
+        Ok(())
+    }
+
     pub fn balance(&mut self, address: &Address) -> Result<U256, Error> {
         let acc = self.account(address)?;
         Ok(acc.balance)
     }
 
-    #[allow(dead_code)]
-    pub fn exist(&self, address: &Address) -> bool {
-        self.provider.exist(address)
+    pub fn code(&mut self, address: &Address) -> Result<&[u8], Error> {
+        let acc = self.account(address)?;
+        Ok(&acc.code)
+    }
+
+    /// Whether the account exists, including accounts created by this execution.
+    pub fn exist(&mut self, address: &Address) -> Result<bool, Error> {
+        // Fetched so the read shows up in the access report.
+        self.fetch_account(address)?;
+        if self.accounts.get(address).is_some_and(|acc| acc.1) {
+            return Ok(true);
+        }
+
+        Ok(self.provider.exist(address))
     }
 
     pub fn timestamp(&self) -> u64 {
-        self.provider.block_number()
+        self.env.timestamp
     }
 
     pub fn block_number(&self) -> u64 {
-        self.provider.block_number()
+        self.env.number
     }
 
     pub fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
         self.provider.block_hash(block_no)
     }
 
-    pub fn block_author(&self) -> Result<Address, Error> {
-        self.provider.block_author()
+    pub fn block_author(&self) -> Address {
+        self.env.author
+    }
+
+    pub fn difficulty(&self) -> U256 {
+        self.env.difficulty
     }
 
-    pub fn difficulty(&self) -> Result<U256, Error> {
-        self.provider.difficulty()
+    pub fn gas_limit(&self) -> U256 {
+        self.env.gas_limit
     }
 
-    pub fn gas_limit(&self) -> Result<U256, Error> {
-        self.provider.gas_limit()
+    pub fn chain_id(&self) -> U256 {
+        self.env.chain_id
     }
 
     pub fn storage_at(&mut self, address: &Address, key: &H256) -> Result<H256, Error> {
@@ -86,16 +122,59 @@ impl<'a> State<'a> {
 
         let acc = self.account(address)?;
 
-        if let Some(v) = acc.storage.get(key) {
-            Ok(v.0)
-        } else {
-            Ok(H256::zero())
+        match acc.storage.get(key) {
+            Some((Some(v), _)) => Ok(*v),
+            _ => Ok(H256::zero()),
         }
     }
 
+    pub fn storage_contains(&mut self, address: &Address, key: &H256) -> Result<bool, Error> {
+        self.fetch_storage(address, key)?;
+
+        let acc = self.account(address)?;
+        Ok(acc.storage.get(key).is_some_and(|v| v.0.is_some()))
+    }
+
+    /// Whether the slot existed before the execution, however it was changed since.
+    pub fn storage_existed(&mut self, address: &Address, key: &H256) -> Result<bool, Error> {
+        self.fetch_storage(address, key)?;
+
+        let acc = self.account(address)?;
+        Ok(acc.original_storage.contains(key))
+    }
+
     pub fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) {
         let acc = self.account_mut(address).unwrap();
-        acc.0.storage.insert(*key, (*value, true));
+        acc.0.storage.insert(*key, (Some(*value), true));
+    }
+
+    pub fn remove_storage(&mut self, address: &Address, key: &H256) {
+        let acc = self.account_mut(address).unwrap();
+        acc.0.storage.insert(*key, (None, true));
+    }
+
+    pub fn storage_bytes_at(&mut self, address: &Address, key: &H256) -> Result<&[u8], Error> {
+        self.fetch_storage_bytes(address, key)?;
+
+        let acc = self.account(address)?;
+        Ok(acc
+            .storage_bytes
+            .get(key)
+            .map(|v| v.0.as_slice())
+            .unwrap_or(&[]))
+    }
+
+    /// Whether the byte slot was non-empty before the execution, however it was changed since.
+    pub fn storage_bytes_existed(&mut self, address: &Address, key: &H256) -> Result<bool, Error> {
+        self.fetch_storage_bytes(address, key)?;
+
+        let acc = self.account(address)?;
+        Ok(acc.original_storage_bytes.contains(key))
+    }
+
+    pub fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: Vec<u8>) {
+        let acc = self.account_mut(address).unwrap();
+        acc.0.storage_bytes.insert(*key, (value, true));
     }
 
     fn account_mut(&mut self, address: &Address) -> Result<&mut (AccountInfo, bool), Error> {
@@ -121,6 +200,11 @@ impl<'a> State<'a> {
             if acc.1 {
                 if !self.provider.exist(addr) {
                     self.provider.create_contract(addr, &acc.0.code)?;
+                    // Accounts are created empty, e.g. a sender the provider didn't know.
+                    if !acc.0.balance.is_zero() || !acc.0.nonce.is_zero() {
+                        self.provider
+                            .update_account(addr, &acc.0.balance, &acc.0.nonce)?;
+                    }
                 } else {
                     self.provider
                         .update_account(addr, &acc.0.balance, &acc.0.nonce)?;
@@ -128,8 +212,16 @@ impl<'a> State<'a> {
             }
 
             for (key, val) in &acc.0.storage {
+                match val {
+                    (Some(value), true) => self.provider.set_storage(addr, key, value)?,
+                    (None, true) => self.provider.remove_storage(addr, key)?,
+                    (_, false) => {}
+                }
+            }
+
+            for (key, val) in &acc.0.storage_bytes {
                 if val.1 {
-                    self.provider.set_storage(addr, key, &val.0)?;
+                    self.provider.set_storage_bytes(addr, key, &val.0)?;
                 }
             }
         }
@@ -137,20 +229,41 @@ impl<'a> State<'a> {
         Ok(())
     }
 
+    pub fn access_report(&self) -> AccessReport {
+        let mut report = AccessReport::default();
+        for (addr, acc) in &self.accounts {
+            report.accounts.push((*addr, access(acc.1)));
+
+            for (key, val) in &acc.0.storage {
+                report.storage.push((*addr, *key, access(val.1)));
+            }
+
+            for (key, val) in &acc.0.storage_bytes {
+                report.storage_bytes.push((*addr, *key, access(val.1)));
+            }
+        }
+
+        report
+    }
+
+    /// Failed reads are cached as empty entries, except for `InvalidProof`,
+    /// `MissingWitness` and `ReplayDiverged`: a value which could not be proven,
+    /// which the witness does not cover, or which a replay can't answer, must
+    /// abort the execution instead of reading as empty.
     fn fetch_account(&mut self, address: &Address) -> Result<(), Error> {
         if self.accounts.contains_key(address) {
             return Ok(());
         }
 
-        if let Ok(acc) = self.provider.account(address) {
-            let acc = AccountInfo::new(acc.balance, acc.nonce, acc.code);
-            self.accounts.insert(*address, (acc, false));
-            Ok(())
-        } else {
-            let acc = AccountInfo::new(U256::zero(), U256::from(1), vec![]);
-            self.accounts.insert(*address, (acc, false));
-            Ok(())
-        }
+        let acc = match self.provider.account(address) {
+            Ok(acc) => AccountInfo::new(acc.nonce, acc.balance, acc.code),
+            Err(err @ Error::InvalidProof { .. })
+            | Err(err @ Error::MissingWitness { .. })
+            | Err(err @ Error::ReplayDiverged { .. }) => return Err(err),
+            Err(_) => AccountInfo::new(U256::zero(), U256::zero(), vec![]),
+        };
+        self.accounts.insert(*address, (acc, false));
+        Ok(())
     }
 
     fn fetch_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
@@ -159,13 +272,53 @@ impl<'a> State<'a> {
             return Ok(());
         }
 
-        if let Ok(value) = self.provider.storage_at(address, key) {
-            let acc = self.account_mut(address)?;
-            acc.0.storage.insert(*key, (value, false));
-            Ok(())
-        } else {
-            debug!("Not storage at {:?}", key);
-            Ok(())
+        let value = match self.provider.storage_at(address, key) {
+            Ok(value) => Some(value),
+            Err(err @ Error::InvalidProof { .. })
+            | Err(err @ Error::MissingWitness { .. })
+            | Err(err @ Error::ReplayDiverged { .. }) => return Err(err),
+            Err(_) => {
+                debug!("Not storage at {:?}", key);
+                None
+            }
+        };
+        let acc = self.account_mut(address)?;
+        if value.is_some() {
+            acc.0.original_storage.insert(*key);
+        }
+        acc.0.storage.insert(*key, (value, false));
+        Ok(())
+    }
+
+    fn fetch_storage_bytes(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        let acc = self.account(address)?;
+        if acc.storage_bytes.contains_key(key) {
+            return Ok(());
+        }
+
+        let value = match self.provider.storage_bytes_at(address, key) {
+            Ok(value) => value,
+            Err(err @ Error::InvalidProof { .. })
+            | Err(err @ Error::MissingWitness { .. })
+            | Err(err @ Error::ReplayDiverged { .. }) => return Err(err),
+            Err(_) => {
+                debug!("Not storage bytes at {:?}", key);
+                Vec::new()
+            }
+        };
+        let acc = self.account_mut(address)?;
+        if !value.is_empty() {
+            acc.0.original_storage_bytes.insert(*key);
         }
+        acc.0.storage_bytes.insert(*key, (value, false));
+        Ok(())
+    }
+}
+
+fn access(dirty: bool) -> Access {
+    if dirty {
+        Access::Write
+    } else {
+        Access::Read
     }
 }
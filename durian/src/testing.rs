@@ -0,0 +1,118 @@
+//! Helpers shared by the unit tests.
+
+use address::Address;
+use block::BlockEnv;
+use dump::{DumpAccount, StateDump};
+use error::Error;
+use execute::{self, ExecutionOutcome};
+use primitive_types::{H256, U256};
+use provider::{Provider, StateAccount};
+use transaction::Transaction;
+use wat;
+
+/// Provider which keeps the whole state in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    pub state: StateDump,
+}
+
+impl MemoryProvider {
+    /// Adds an account holding `code`, written in the wasm text format.
+    pub fn deploy(&mut self, address: Address, code: &str) {
+        self.state.accounts.insert(
+            address,
+            DumpAccount {
+                code: wat::parse_str(code).expect("Test contracts are valid; qed"),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn account_mut(&mut self, address: &Address) -> &mut DumpAccount {
+        self.state.accounts.entry(*address).or_default()
+    }
+}
+
+/// Sender of the transactions made by `call`.
+pub fn sender() -> Address {
+    Address::repeat_byte(0xaa)
+}
+
+/// Transaction calling the contract at `address` with `args`.
+pub fn call(address: Address, args: Vec<u8>) -> Transaction {
+    Transaction::make_call(
+        sender(),
+        address,
+        U256::zero(),
+        U256::from(1_000_000),
+        U256::zero(),
+        args,
+    )
+}
+
+/// Executes `transaction` in an empty block, panicking if it could not be executed at all.
+pub fn execute(provider: &mut dyn Provider, transaction: &Transaction) -> ExecutionOutcome {
+    execute::execute(provider, &BlockEnv::default(), transaction).unwrap()
+}
+
+impl Provider for MemoryProvider {
+    fn exist(&self, address: &Address) -> bool {
+        self.state.accounts.contains_key(address)
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        match self.state.accounts.get(address) {
+            Some(acc) => Ok(StateAccount {
+                nonce: acc.nonce,
+                balance: acc.balance,
+                code: acc.code.clone(),
+            }),
+            None => Err(Error::InvalidAddress { address: *address }),
+        }
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        let acc = self.account_mut(address);
+        acc.balance = *bal;
+        acc.nonce = *nonce;
+        Ok(())
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        self.account_mut(address).code = code.clone();
+        Ok(())
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        match self.state.accounts.get(address).and_then(|acc| acc.storage.get(key)) {
+            Some(value) => Ok(*value),
+            None => Err(Error::InvalidStorageKey { key: *key }),
+        }
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        self.account_mut(address).storage.insert(*key, *value);
+        Ok(())
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        self.account_mut(address).storage.remove(key);
+        Ok(())
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        match self.state.accounts.get(address).and_then(|acc| acc.storage_bytes.get(key)) {
+            Some(value) => Ok(value.clone()),
+            None => Err(Error::InvalidStorageKey { key: *key }),
+        }
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        self.account_mut(address).storage_bytes.insert(*key, value.to_vec());
+        Ok(())
+    }
+
+    fn block_hash(&self, _block_no: u64) -> Result<H256, Error> {
+        Ok(H256::zero())
+    }
+}
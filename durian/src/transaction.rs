@@ -1,9 +1,15 @@
 use super::Bytes;
+use error::Error;
 use parity_wasm::peek_size;
 use primitive_types::{H256, U256};
+use rlp::{self, Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use address::Address;
+use utils;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Version of the transaction encoding, which is the first item of the list.
+pub const TRANSACTION_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
     /// Create creates new contract.
     /// Code + salt
@@ -13,7 +19,36 @@ pub enum Action {
     Call(Address),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl Encodable for Action {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            Action::Create(code, salt) => {
+                s.begin_list(3);
+                s.append(&0u8);
+                s.append(code);
+                s.append(salt);
+            }
+            Action::Call(address) => {
+                s.begin_list(2);
+                s.append(&1u8);
+                s.append(address);
+            }
+        }
+    }
+}
+
+impl Decodable for Action {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        match rlp.val_at::<u8>(0)? {
+            0 if rlp.item_count()? == 3 => Ok(Action::Create(rlp.val_at(1)?, rlp.val_at(2)?)),
+            1 if rlp.item_count()? == 2 => Ok(Action::Call(rlp.val_at(1)?)),
+            0 | 1 => Err(DecoderError::RlpIncorrectListLen),
+            _ => Err(DecoderError::Custom("Unknown action")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Transaction {
     pub sender: Address,
     pub value: U256,
@@ -23,6 +58,40 @@ pub struct Transaction {
     pub args: Bytes,
 }
 
+/// Encoded as the RLP list `[version, sender, value, gas, gas_price, action, args]`.
+impl Encodable for Transaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(7);
+        s.append(&TRANSACTION_VERSION);
+        s.append(&self.sender);
+        s.append(&self.value);
+        s.append(&self.gas);
+        s.append(&self.gas_price);
+        s.append(&self.action);
+        s.append(&self.args);
+    }
+}
+
+impl Decodable for Transaction {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.val_at::<u8>(0)? != TRANSACTION_VERSION {
+            return Err(DecoderError::Custom("Unsupported transaction version"));
+        }
+        if rlp.item_count()? != 7 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        Ok(Transaction {
+            sender: rlp.val_at(1)?,
+            value: rlp.val_at(2)?,
+            gas: rlp.val_at(3)?,
+            gas_price: rlp.val_at(4)?,
+            action: rlp.val_at(5)?,
+            args: rlp.val_at(6)?,
+        })
+    }
+}
+
 impl Transaction {
     pub fn make_create_embedded_code(
         sender: Address,
@@ -82,4 +151,248 @@ impl Transaction {
             args,
         }
     }
+
+    /// Canonical encoding of the transaction.
+    pub fn encode(&self) -> Bytes {
+        rlp::encode(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        decode_exact(bytes)
+    }
+
+    /// Identifier of the transaction sent with `nonce` on the chain `chain_id`:
+    /// the keccak hash of the RLP list `[nonce, chain_id, transaction]`, so the
+    /// same transaction sent twice, or to two chains, has two identifiers.
+    pub fn hash(&self, nonce: &U256, chain_id: &U256) -> H256 {
+        let mut s = RlpStream::new_list(3);
+        s.append(nonce);
+        s.append(chain_id);
+        s.append(self);
+        utils::keccak(s.out())
+    }
+
+    /// Hash the sender signs, covering every field but `sender` itself.
+    ///
+    /// It is the keccak hash of the RLP list
+    /// `[nonce, chain_id, value, gas, gas_price, action, args]`, where `action`
+    /// is `[0, code, salt]` for a create and `[1, address]` for a call.
+    pub fn signing_hash(&self, nonce: &U256, chain_id: &U256) -> H256 {
+        let mut s = RlpStream::new_list(7);
+        s.append(nonce);
+        s.append(chain_id);
+        s.append(&self.value);
+        s.append(&self.gas);
+        s.append(&self.gas_price);
+        s.append(&self.action);
+        s.append(&self.args);
+        utils::keccak(s.out())
+    }
+}
+
+fn decode_exact<T: Decodable>(bytes: &[u8]) -> Result<T, Error> {
+    let rlp = Rlp::new(bytes);
+    let malformed = |err: DecoderError| Error::MalformedTransaction {
+        msg: format!("{}", err),
+    };
+    if rlp.payload_info().map_err(malformed)?.total() != bytes.len() {
+        return Err(malformed(DecoderError::RlpIsTooBig));
+    }
+    rlp.as_val().map_err(malformed)
+}
+
+/// Transaction together with the secp256k1 signature of its sender.
+///
+/// The nonce and chain id are signed along with the transaction, so a signed
+/// transaction can neither be replayed nor be sent to another chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub transaction: Transaction,
+    pub nonce: U256,
+    pub chain_id: U256,
+    pub r: H256,
+    pub s: H256,
+    /// Recovery id, 0 or 1.
+    pub v: u8,
+}
+
+impl SignedTransaction {
+    /// Signs `transaction` with `secret`, setting its sender to the address of the key.
+    pub fn sign(
+        transaction: Transaction,
+        nonce: U256,
+        chain_id: U256,
+        secret: &H256,
+    ) -> Result<Self, Error> {
+        let sender = utils::secret_to_address(secret).ok_or(Error::InvalidSignature)?;
+        let transaction = Transaction {
+            sender,
+            ..transaction
+        };
+        let hash = transaction.signing_hash(&nonce, &chain_id);
+        let signature = utils::sign(&hash, secret).ok_or(Error::InvalidSignature)?;
+
+        Ok(SignedTransaction {
+            transaction,
+            nonce,
+            chain_id,
+            r: H256::from_slice(&signature[..32]),
+            s: H256::from_slice(&signature[32..64]),
+            v: signature[64],
+        })
+    }
+
+    pub fn signing_hash(&self) -> H256 {
+        self.transaction.signing_hash(&self.nonce, &self.chain_id)
+    }
+
+    /// Canonical encoding of the signed transaction.
+    pub fn encode(&self) -> Bytes {
+        rlp::encode(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        decode_exact(bytes)
+    }
+
+    /// Identifier of the signed transaction: the keccak hash of its encoding,
+    /// which covers the nonce and chain id along with the transaction.
+    pub fn hash(&self) -> H256 {
+        utils::keccak(self.encode())
+    }
+
+    /// Address of the key which signed the transaction.
+    ///
+    /// Only the recovery ids 0 and 1 made by `sign` are accepted, not 27 and 28:
+    /// each signature must have a single encoding.
+    pub fn recover_sender(&self) -> Result<Address, Error> {
+        if self.v > 1 {
+            return Err(Error::InvalidSignature);
+        }
+
+        let mut signature = [0u8; 65];
+        signature[..32].copy_from_slice(self.r.as_bytes());
+        signature[32..64].copy_from_slice(self.s.as_bytes());
+        signature[64] = self.v;
+        utils::ecrecover(&self.signing_hash(), &signature).ok_or(Error::InvalidSignature)
+    }
+
+    /// Returns the transaction if it was signed by its sender.
+    pub fn verify(&self) -> Result<&Transaction, Error> {
+        if self.recover_sender()? != self.transaction.sender {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(&self.transaction)
+    }
+}
+
+/// Encoded as the RLP list `[transaction, nonce, chain_id, r, s, v]`.
+impl Encodable for SignedTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+        s.append(&self.transaction);
+        s.append(&self.nonce);
+        s.append(&self.chain_id);
+        s.append(&self.r);
+        s.append(&self.s);
+        s.append(&self.v);
+    }
+}
+
+impl Decodable for SignedTransaction {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 6 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        Ok(SignedTransaction {
+            transaction: rlp.val_at(0)?,
+            nonce: rlp.val_at(1)?,
+            chain_id: rlp.val_at(2)?,
+            r: rlp.val_at(3)?,
+            s: rlp.val_at(4)?,
+            v: rlp.val_at(5)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction() -> Transaction {
+        Transaction::make_call(
+            Address::repeat_byte(0xaa),
+            Address::repeat_byte(1),
+            U256::from(7),
+            U256::from(100_000),
+            U256::from(1),
+            vec![1, 2, 3],
+        )
+    }
+
+    fn sign(nonce: u64) -> SignedTransaction {
+        let secret = H256::repeat_byte(0x11);
+        SignedTransaction::sign(transaction(), U256::from(nonce), U256::one(), &secret).unwrap()
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let call = transaction();
+        assert_eq!(Transaction::decode(&call.encode()).unwrap(), call);
+
+        let create = Transaction::make_create(
+            Address::repeat_byte(0xaa),
+            U256::zero(),
+            U256::from(100_000),
+            U256::from(1),
+            vec![0, 0x61, 0x73, 0x6d],
+            vec![],
+            H256::repeat_byte(2),
+        );
+        assert_eq!(Transaction::decode(&create.encode()).unwrap(), create);
+
+        let signed = sign(3);
+        assert_eq!(SignedTransaction::decode(&signed.encode()).unwrap(), signed);
+    }
+
+    #[test]
+    fn decoding_rejects_malformed_transactions() {
+        let mut encoded = transaction().encode();
+        encoded.push(0);
+        assert!(Transaction::decode(&encoded).is_err());
+
+        let mut s = RlpStream::new_list(2);
+        s.append(&(TRANSACTION_VERSION + 1));
+        s.append(&transaction().sender);
+        assert!(Transaction::decode(&s.out()).is_err());
+
+        let mut encoded = sign(0).encode();
+        encoded.push(0);
+        assert!(SignedTransaction::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn hash_depends_on_nonce_and_chain_id() {
+        let transaction = transaction();
+        let (zero, one) = (U256::zero(), U256::one());
+        assert_eq!(transaction.hash(&zero, &one), transaction.clone().hash(&zero, &one));
+        assert_ne!(transaction.hash(&zero, &one), transaction.hash(&one, &one));
+        assert_ne!(transaction.hash(&zero, &one), transaction.hash(&zero, &U256::from(2)));
+
+        assert_eq!(sign(0).hash(), sign(0).hash());
+        assert_ne!(sign(0).hash(), sign(1).hash());
+    }
+
+    #[test]
+    fn recovery_id_must_be_zero_or_one() {
+        let secret = H256::repeat_byte(0x11);
+        let mut signed =
+            SignedTransaction::sign(transaction(), U256::zero(), U256::one(), &secret).unwrap();
+        assert!(signed.v <= 1);
+        assert!(signed.verify().is_ok());
+
+        signed.v += 27;
+        assert_eq!(signed.recover_sender(), Err(Error::InvalidSignature));
+    }
 }
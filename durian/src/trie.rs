@@ -0,0 +1,334 @@
+use error::Error;
+use primitive_types::H256;
+use rlp::{self, DecoderError, Rlp, RlpStream};
+use std::collections::{BTreeMap, HashMap};
+use utils;
+
+/// Root of a trie without any entries.
+pub fn empty_root() -> H256 {
+    utils::keccak(rlp::NULL_RLP)
+}
+
+/// In-memory Merkle Patricia trie, encoded the same way as Ethereum's.
+///
+/// Keys are used as given. The state and storage tries hash them first, so
+/// that the trie stays balanced whatever keys the contracts pick.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trie {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Trie {
+    pub fn new() -> Self {
+        Trie::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).map(|value| value.as_slice())
+    }
+
+    /// Inserts `value` under `key`. An empty value removes the key.
+    pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+        if value.is_empty() {
+            self.entries.remove(key);
+        } else {
+            self.entries.insert(key.to_vec(), value);
+        }
+    }
+
+    pub fn remove(&mut self, key: &[u8]) {
+        self.entries.remove(key);
+    }
+
+    pub fn root(&self) -> H256 {
+        utils::keccak(self.encode_root(None, &mut vec![]))
+    }
+
+    /// Nodes on the path to `key`, starting from the root.
+    ///
+    /// Nodes short enough to be inlined in their parent are left out. The
+    /// proof shows either the value of `key`, or that the trie has none.
+    pub fn prove(&self, key: &[u8]) -> Vec<Vec<u8>> {
+        let mut proof = vec![];
+        let root = self.encode_root(Some(&nibbles(key)), &mut proof);
+        proof.push(root);
+        proof.reverse();
+        proof
+    }
+
+    fn encode_root(&self, path: Option<&[u8]>, proof: &mut Vec<Vec<u8>>) -> Vec<u8> {
+        let entries: Vec<(Vec<u8>, &[u8])> = self
+            .entries
+            .iter()
+            .map(|(key, value)| (nibbles(key), value.as_slice()))
+            .collect();
+        encode_node(&entries, 0, path, proof)
+    }
+}
+
+/// Checks `proof` against `root` and returns the value it shows for `key`.
+///
+/// `Ok(None)` means the proof shows that the trie has no value for `key`.
+pub fn verify_proof(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, Error> {
+    let nodes: HashMap<H256, &[u8]> = proof
+        .iter()
+        .map(|node| (utils::keccak(node), node.as_slice()))
+        .collect();
+    let key = nibbles(key);
+    let mut depth = 0;
+    let mut node = match nodes.get(root) {
+        Some(node) => Rlp::new(node),
+        None => return Err(invalid_proof("Missing root node")),
+    };
+
+    loop {
+        if node.is_empty() {
+            return Ok(None);
+        }
+        let next = match node.item_count().map_err(malformed_node)? {
+            17 => {
+                if depth == key.len() {
+                    let value = node.at(16).and_then(|value| value.data()).map_err(malformed_node)?;
+                    return Ok(if value.is_empty() { None } else { Some(value.to_vec()) });
+                }
+                let child = node.at(key[depth] as usize).map_err(malformed_node)?;
+                depth += 1;
+                child
+            }
+            2 => {
+                let (path, leaf) = decode_path(node.at(0).and_then(|path| path.data()).map_err(malformed_node)?)?;
+                if !key[depth..].starts_with(&path) {
+                    return Ok(None);
+                }
+                depth += path.len();
+                if leaf {
+                    if depth != key.len() {
+                        return Ok(None);
+                    }
+                    return Ok(Some(node.val_at(1).map_err(malformed_node)?));
+                }
+                node.at(1).map_err(malformed_node)?
+            }
+            _ => return Err(invalid_proof("Unknown node kind")),
+        };
+
+        node = if next.is_list() {
+            next
+        } else {
+            let hash = next.data().map_err(malformed_node)?;
+            if hash.is_empty() {
+                return Ok(None);
+            }
+            if hash.len() != 32 {
+                return Err(invalid_proof("Invalid node reference"));
+            }
+            match nodes.get(&H256::from_slice(hash)) {
+                Some(node) => Rlp::new(node),
+                None => return Err(invalid_proof("Missing node")),
+            }
+        };
+    }
+}
+
+fn invalid_proof(msg: &str) -> Error {
+    Error::InvalidProof {
+        msg: msg.to_string(),
+    }
+}
+
+fn malformed_node(err: DecoderError) -> Error {
+    Error::InvalidProof {
+        msg: format!("Malformed node: {}", err),
+    }
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|byte| vec![byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Hex-prefix encoding of `path`, flagging leaves and odd lengths in the first nibble.
+fn encode_path(path: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(path.len() / 2 + 1);
+    let rest = if path.len() % 2 == 1 {
+        encoded.push(flag | 0x10 | path[0]);
+        &path[1..]
+    } else {
+        encoded.push(flag);
+        path
+    };
+    for pair in rest.chunks(2) {
+        encoded.push(pair[0] << 4 | pair[1]);
+    }
+    encoded
+}
+
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), Error> {
+    let first = match encoded.first() {
+        Some(first) => *first,
+        None => return Err(invalid_proof("Empty node path")),
+    };
+    if first >> 4 > 3 {
+        return Err(invalid_proof("Invalid node path"));
+    }
+    let mut path = nibbles(&encoded[1..]);
+    if first & 0x10 != 0 {
+        path.insert(0, first & 0x0f);
+    }
+    Ok((path, first & 0x20 != 0))
+}
+
+/// Encodes the node holding `entries`, which are sorted and share their first
+/// `depth` nibbles.
+///
+/// Nodes lying on `path` which aren't inlined in their parent are pushed to
+/// `proof`, deepest first.
+fn encode_node(
+    entries: &[(Vec<u8>, &[u8])],
+    depth: usize,
+    path: Option<&[u8]>,
+    proof: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+    if entries.is_empty() {
+        return rlp::NULL_RLP.to_vec();
+    }
+
+    if entries.len() == 1 {
+        let (key, value) = &entries[0];
+        let mut s = RlpStream::new_list(2);
+        s.append(&encode_path(&key[depth..], true));
+        s.append(value);
+        return s.out();
+    }
+
+    let shared = shared_prefix(entries, depth);
+    if shared > 0 {
+        let prefix = &entries[0].0[depth..depth + shared];
+        let path = path.filter(|path| path.len() >= depth + shared && &path[depth..depth + shared] == prefix);
+        let child = encode_node(entries, depth + shared, path, proof);
+        let mut s = RlpStream::new_list(2);
+        s.append(&encode_path(prefix, false));
+        append_child(&mut s, child, path.is_some(), proof);
+        return s.out();
+    }
+
+    let mut s = RlpStream::new_list(17);
+    // Keys are unique and sorted, so a key ending here can only come first.
+    let (value, mut rest) = if entries[0].0.len() == depth {
+        (Some(entries[0].1), &entries[1..])
+    } else {
+        (None, entries)
+    };
+    for nibble in 0..16u8 {
+        let count = rest
+            .iter()
+            .take_while(|(key, _)| key[depth] == nibble)
+            .count();
+        if count == 0 {
+            s.append_empty_data();
+            continue;
+        }
+        let path = path.filter(|path| path.len() > depth && path[depth] == nibble);
+        let child = encode_node(&rest[..count], depth + 1, path, proof);
+        append_child(&mut s, child, path.is_some(), proof);
+        rest = &rest[count..];
+    }
+    match value {
+        Some(value) => s.append(&value),
+        None => s.append_empty_data(),
+    };
+    s.out()
+}
+
+/// Nodes shorter than a hash are inlined, the others are referenced by their hash.
+fn append_child(s: &mut RlpStream, child: Vec<u8>, on_path: bool, proof: &mut Vec<Vec<u8>>) {
+    if child.len() < 32 {
+        s.append_raw(&child, 1);
+    } else {
+        s.append(&utils::keccak(&child));
+        if on_path {
+            proof.push(child);
+        }
+    }
+}
+
+fn shared_prefix(entries: &[(Vec<u8>, &[u8])], depth: usize) -> usize {
+    let first = &entries[0].0[depth..];
+    let last = &entries[entries.len() - 1].0[depth..];
+    // Entries are sorted, so what the first and last share, all of them share.
+    first
+        .iter()
+        .zip(last.iter())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(entries: &[(&str, &str)]) -> String {
+        let mut trie = Trie::new();
+        for (key, value) in entries {
+            trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+        }
+        format!("{:x}", trie.root())
+    }
+
+    #[test]
+    fn empty_root() {
+        assert_eq!(
+            format!("{:x}", super::empty_root()),
+            "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+        );
+        assert_eq!(Trie::new().root(), super::empty_root());
+    }
+
+    /// Vectors of the Ethereum trie tests.
+    #[test]
+    fn ethereum_roots() {
+        assert_eq!(
+            root(&[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]),
+            "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+        );
+        // Empty values delete their key.
+        assert_eq!(
+            root(&[
+                ("do", "verb"),
+                ("ether", "wookiedoo"),
+                ("horse", "stallion"),
+                ("shaman", "horse"),
+                ("doge", "coin"),
+                ("ether", ""),
+                ("dog", "puppy"),
+                ("shaman", ""),
+            ]),
+            "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+        );
+    }
+
+    #[test]
+    fn proofs_verify() {
+        let mut trie = Trie::new();
+        for (key, value) in &[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")] {
+            trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+        }
+        let root = trie.root();
+
+        for key in &["doe", "dog", "dogglesworth"] {
+            let proof = trie.prove(key.as_bytes());
+            let value = verify_proof(&root, key.as_bytes(), &proof).unwrap();
+            assert_eq!(value.as_ref().map(|value| &value[..]), trie.get(key.as_bytes()));
+        }
+
+        let proof = trie.prove(b"dogs");
+        assert_eq!(verify_proof(&root, b"dogs", &proof).unwrap(), None);
+
+        let proof = trie.prove(b"dog");
+        assert!(verify_proof(&super::empty_root(), b"dog", &proof).is_err());
+    }
+}
@@ -1,5 +1,11 @@
+use ed25519_dalek::{self, Verifier};
 use keccak_hash::write_keccak;
-use primitive_types::H256;
+use primitive_types::{H160, H256};
+use ripemd160::Ripemd160;
+use secp256k1::curve::Scalar;
+use secp256k1::{self, Message, PublicKey, RecoveryId, SecretKey, Signature};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
 use address::Address;
 
 pub fn keccak<T: AsRef<[u8]>>(s: T) -> H256 {
@@ -8,6 +14,79 @@ pub fn keccak<T: AsRef<[u8]>>(s: T) -> H256 {
     H256(result)
 }
 
+pub fn sha256<T: AsRef<[u8]>>(s: T) -> H256 {
+    H256::from_slice(&Sha256::digest(s.as_ref()))
+}
+
+pub fn ripemd160<T: AsRef<[u8]>>(s: T) -> H160 {
+    H160::from_slice(&Ripemd160::digest(s.as_ref()))
+}
+
+/// Recovers the address which signed `hash`.
+///
+/// `signature` is `r || s || v`, with `v` either 0/1 or 27/28. Like Ethereum
+/// transactions since Homestead, `r` and `s` must be in `[1, n)` and `s` must be
+/// at most `n / 2`, so a signature can't be turned into a second valid one.
+pub fn ecrecover(hash: &H256, signature: &[u8; 65]) -> Option<Address> {
+    let v = match signature[64] {
+        v @ 0..=1 => v,
+        v @ 27..=28 => v - 27,
+        _ => return None,
+    };
+    let mut r_bytes = [0u8; 32];
+    let mut s_bytes = [0u8; 32];
+    r_bytes.copy_from_slice(&signature[..32]);
+    s_bytes.copy_from_slice(&signature[32..64]);
+
+    let mut r = Scalar::default();
+    let mut s = Scalar::default();
+    let overflow = bool::from(r.set_b32(&r_bytes)) || bool::from(s.set_b32(&s_bytes));
+    if overflow || r.is_zero() || s.is_zero() || s.is_high() {
+        return None;
+    }
+
+    let recovery_id = RecoveryId::parse(v).ok()?;
+    let signature = Signature { r, s };
+    let public = secp256k1::recover(&Message::parse(hash.as_fixed_bytes()), &signature, &recovery_id).ok()?;
+    Some(public_to_address(&public))
+}
+
+/// Signs `hash` with the secp256k1 `secret`, as `r || s || v` with `v` being 0 or 1.
+pub fn sign(hash: &H256, secret: &H256) -> Option<[u8; 65]> {
+    let secret = SecretKey::parse(secret.as_fixed_bytes()).ok()?;
+    let (signature, recovery_id) = secp256k1::sign(&Message::parse(hash.as_fixed_bytes()), &secret);
+
+    let mut result = [0u8; 65];
+    result[..64].copy_from_slice(&signature.serialize());
+    result[64] = recovery_id.serialize();
+    Some(result)
+}
+
+/// Address of the account controlled by the secp256k1 `secret`.
+pub fn secret_to_address(secret: &H256) -> Option<Address> {
+    let secret = SecretKey::parse(secret.as_fixed_bytes()).ok()?;
+    Some(public_to_address(&PublicKey::from_secret_key(&secret)))
+}
+
+fn public_to_address(public: &PublicKey) -> Address {
+    // Address is the last 20 bytes of the hash of the public key, without its 0x04 prefix.
+    let public_hash = keccak(&public.serialize()[1..]);
+    Address::from_slice(&public_hash[12..])
+}
+
+/// Checks `signature` of `message` against the ed25519 `public_key`.
+pub fn ed25519_verify(message: &[u8], signature: &[u8; 64], public_key: &[u8; 32]) -> bool {
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature = match ed25519_dalek::Signature::try_from(&signature[..]) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    public_key.verify(message, &signature).is_ok()
+}
+
 pub fn contract_address(sender: &Address, code: &[u8], salt: &H256) -> Address {
     let code_hash = keccak(code);
     let mut buffer = [0u8; 1 + 20 + 32 + 32];
@@ -17,3 +96,59 @@ pub fn contract_address(sender: &Address, code: &[u8], salt: &H256) -> Address {
     &mut buffer[(1 + 20 + 32)..].copy_from_slice(&code_hash[..]);
     From::from(keccak(&buffer[..]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitive_types::U256;
+
+    /// Order of the secp256k1 group.
+    const N: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    fn signed() -> (H256, [u8; 65], Address) {
+        let hash = keccak(b"durian");
+        let secret = H256::repeat_byte(0x11);
+        let signature = sign(&hash, &secret).unwrap();
+        (hash, signature, secret_to_address(&secret).unwrap())
+    }
+
+    #[test]
+    fn recovers_signer() {
+        let (hash, mut signature, signer) = signed();
+        assert_eq!(ecrecover(&hash, &signature), Some(signer));
+
+        signature[64] += 27;
+        assert_eq!(ecrecover(&hash, &signature), Some(signer));
+    }
+
+    #[test]
+    fn rejects_high_s() {
+        let (hash, mut signature, _) = signed();
+        let n: U256 = N.parse().unwrap();
+
+        // `(r, n - s)` with the other recovery id is the same signature, mirrored.
+        let s = U256::from_big_endian(&signature[32..64]);
+        (n - s).to_big_endian(&mut signature[32..64]);
+        signature[64] ^= 1;
+        assert_eq!(ecrecover(&hash, &signature), None);
+    }
+
+    #[test]
+    fn rejects_out_of_range_r() {
+        let (hash, mut signature, _) = signed();
+        let n: U256 = N.parse().unwrap();
+
+        // Would be reduced to the original `r` if overflows were accepted.
+        let r = U256::from_big_endian(&signature[..32]);
+        if let Some(r) = r.checked_add(n) {
+            r.to_big_endian(&mut signature[..32]);
+            assert_eq!(ecrecover(&hash, &signature), None);
+        }
+
+        n.to_big_endian(&mut signature[..32]);
+        assert_eq!(ecrecover(&hash, &signature), None);
+
+        signature[..32].copy_from_slice(&[0; 32]);
+        assert_eq!(ecrecover(&hash, &signature), None);
+    }
+}
@@ -0,0 +1,208 @@
+use address::Address;
+use dump::{self, StateProof, TrieAccount};
+use error::Error;
+use primitive_types::{H256, U256};
+use provider::{Provider, StateAccount};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use trie;
+use utils;
+
+/// Provider decorator which checks every read against a state root.
+///
+/// Each account and storage value returned by the inner provider must match
+/// what `proof` shows for it under `state_root`, otherwise the read fails with
+/// `Error::InvalidProof` and so does the execution. `exist` can't fail, so its
+/// mismatches are only remembered; `finish` reports them once the execution is
+/// over. Reads of entries this execution already wrote are not checked, since
+/// the proofs only cover the pre-state.
+pub struct VerifyingProvider<'a> {
+    provider: &'a mut dyn Provider,
+    state_root: H256,
+    proof: StateProof,
+    accounts: RefCell<BTreeMap<Address, Option<TrieAccount>>>,
+    failures: RefCell<Vec<String>>,
+    written_accounts: BTreeSet<Address>,
+    written_storage: BTreeSet<(Address, H256)>,
+    written_storage_bytes: BTreeSet<(Address, H256)>,
+}
+
+impl<'a> VerifyingProvider<'a> {
+    pub fn new(provider: &'a mut dyn Provider, state_root: H256, proof: StateProof) -> Self {
+        VerifyingProvider {
+            provider,
+            state_root,
+            proof,
+            accounts: RefCell::new(BTreeMap::new()),
+            failures: RefCell::new(Vec::new()),
+            written_accounts: BTreeSet::new(),
+            written_storage: BTreeSet::new(),
+            written_storage_bytes: BTreeSet::new(),
+        }
+    }
+
+    /// Returns an error if any read of the execution did not match its proof.
+    pub fn finish(self) -> Result<(), Error> {
+        let failures = self.failures.into_inner();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidProof {
+                msg: failures.join(", "),
+            })
+        }
+    }
+
+    fn fail(&self, msg: String) -> Error {
+        self.failures.borrow_mut().push(msg.clone());
+        Error::InvalidProof { msg }
+    }
+
+    /// Records a proof which did not verify, prefixing its message with `what`.
+    fn rejected(&self, what: String, err: Error) -> Error {
+        match err {
+            Error::InvalidProof { msg } => self.fail(format!("{}: {}", what, msg)),
+            err => self.fail(format!("{}: {}", what, err)),
+        }
+    }
+
+    /// The account as proven against the state root, `None` if it doesn't exist.
+    fn proven_account(&self, address: &Address) -> Result<Option<TrieAccount>, Error> {
+        if let Some(account) = self.accounts.borrow().get(address) {
+            return Ok(account.clone());
+        }
+
+        let proof = match self.proof.accounts.get(address) {
+            Some(proof) => proof,
+            None => return Err(self.fail(format!("no proof of account {:?}", address))),
+        };
+        let account = dump::verify_account_proof(&self.state_root, address, proof)
+            .map_err(|err| self.rejected(format!("account {:?}", address), err))?;
+        self.accounts.borrow_mut().insert(*address, account.clone());
+        Ok(account)
+    }
+
+    fn storage_root(&self, address: &Address) -> Result<H256, Error> {
+        Ok(self
+            .proven_account(address)?
+            .map_or_else(trie::empty_root, |account| account.storage_root))
+    }
+
+    fn storage_proof<'p>(
+        &self,
+        proofs: &'p BTreeMap<Address, BTreeMap<H256, Vec<Vec<u8>>>>,
+        address: &Address,
+        key: &H256,
+    ) -> Result<&'p [Vec<u8>], Error> {
+        match proofs.get(address).and_then(|proofs| proofs.get(key)) {
+            Some(proof) => Ok(proof),
+            None => Err(self.fail(format!("no proof of storage {:?} of {:?}", key, address))),
+        }
+    }
+}
+
+impl<'a> Provider for VerifyingProvider<'a> {
+    fn exist(&self, address: &Address) -> bool {
+        let exist = self.provider.exist(address);
+        if self.written_accounts.contains(address) {
+            return exist;
+        }
+
+        match self.proven_account(address) {
+            Ok(account) => {
+                if exist != account.is_some() {
+                    self.fail(format!("existence of {:?}", address));
+                }
+                account.is_some()
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        let result = self.provider.account(address);
+        if self.written_accounts.contains(address) {
+            return result;
+        }
+
+        match (result, self.proven_account(address)?) {
+            (Ok(acc), Some(proven)) => {
+                if acc.nonce != proven.nonce
+                    || acc.balance != proven.balance
+                    || utils::keccak(&acc.code) != proven.code_hash
+                {
+                    return Err(self.fail(format!("account {:?} does not match", address)));
+                }
+                Ok(acc)
+            }
+            (Err(err), None) => Err(err),
+            (Ok(_), None) => Err(self.fail(format!("account {:?} is not in the state", address))),
+            (Err(_), Some(_)) => Err(self.fail(format!("account {:?} is missing", address))),
+        }
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        self.written_accounts.insert(*address);
+        self.provider.update_account(address, bal, nonce)
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        self.written_accounts.insert(*address);
+        self.provider.create_contract(address, code)
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        let result = self.provider.storage_at(address, key);
+        if self.written_storage.contains(&(*address, *key)) {
+            return result;
+        }
+
+        let storage_root = self.storage_root(address)?;
+        let proof = self.storage_proof(&self.proof.storage, address, key)?;
+        let proven = dump::verify_storage_proof(&storage_root, key, proof)
+            .map_err(|err| self.rejected(format!("storage {:?} of {:?}", key, address), err))?;
+        match (result, proven) {
+            (Ok(value), Some(proven)) if value == proven => Ok(value),
+            (Err(err), None) => Err(err),
+            _ => Err(self.fail(format!("storage {:?} of {:?} does not match", key, address))),
+        }
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        self.written_storage.insert((*address, *key));
+        self.provider.set_storage(address, key, value)
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        self.written_storage.insert((*address, *key));
+        self.provider.remove_storage(address, key)
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        let result = self.provider.storage_bytes_at(address, key);
+        if self.written_storage_bytes.contains(&(*address, *key)) {
+            return result;
+        }
+
+        let storage_root = self.storage_root(address)?;
+        let proof = self.storage_proof(&self.proof.storage_bytes, address, key)?;
+        let proven = dump::verify_storage_bytes_proof(&storage_root, key, proof)
+            .map_err(|err| self.rejected(format!("storage bytes {:?} of {:?}", key, address), err))?;
+        // The trie leaves empty values out, so those read like missing ones.
+        match (result, proven) {
+            (Ok(value), Some(proven)) if value == proven => Ok(value),
+            (Ok(value), None) if value.is_empty() => Ok(value),
+            (Err(err), None) => Err(err),
+            _ => Err(self.fail(format!("storage bytes {:?} of {:?} does not match", key, address))),
+        }
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        self.written_storage_bytes.insert((*address, *key));
+        self.provider.set_storage_bytes(address, key, value)
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        self.provider.block_hash(block_no)
+    }
+}
@@ -26,10 +26,20 @@ pub struct WasmCosts {
 	pub opcodes_mul: u32,
 	/// Cost of wasm opcode is calculated as TABLE_ENTRY_COST * `opcodes_mul` / `opcodes_div`
 	pub opcodes_div: u32,
+	/// Cost of recovering the signer of a secp256k1 signature
+	pub ecrecover: u32,
+	/// Cost of verifying an ed25519 signature
+	pub ed25519_verify: u32,
 	/// Whether create2 extern function is activated.
 	pub have_create2: bool,
 	/// Whether gasleft extern function is activated.
 	pub have_gasleft: bool,
+	/// Whether balance extern function is activated.
+	pub have_balance: bool,
+	/// Whether code_size extern function is activated.
+	pub have_code_size: bool,
+	/// Whether account_exists extern function is activated.
+	pub have_account_exists: bool,
 }
 
 impl Default for WasmCosts {
@@ -47,8 +57,13 @@ impl Default for WasmCosts {
 			max_stack_height: 64*1024,
 			opcodes_mul: 3,
 			opcodes_div: 8,
+			ecrecover: 8000,
+			ed25519_verify: 6000,
 			have_create2: false,
 			have_gasleft: false,
+			have_balance: false,
+			have_code_size: false,
+			have_account_exists: false,
 		}
     }
 }
\ No newline at end of file
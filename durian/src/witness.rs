@@ -0,0 +1,381 @@
+use address::Address;
+use error::Error;
+use primitive_types::{H256, U256};
+use provider::{Provider, StateAccount};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Outcome of a provider read as it was observed by the recorder.
+///
+/// Failed reads are kept as well, because `State` treats a failing `account`
+/// or `storage_at` call as an empty entry and the replay must do the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Entry<T> {
+    Found(T),
+    Failed(String),
+}
+
+impl<T: Clone> Entry<T> {
+    pub(crate) fn from_result(result: &Result<T, Error>) -> Self {
+        match result {
+            Ok(val) => Entry::Found(val.clone()),
+            Err(err) => Entry::Failed(format!("{}", err)),
+        }
+    }
+
+    pub(crate) fn to_result(&self) -> Result<T, Error> {
+        match self {
+            Entry::Found(val) => Ok(val.clone()),
+            Entry::Failed(msg) => Err(Error::Other { msg: msg.clone() }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessAccount {
+    pub nonce: U256,
+    pub balance: U256,
+    pub code: Vec<u8>,
+}
+
+impl WitnessAccount {
+    pub(crate) fn entry(result: &Result<StateAccount, Error>) -> Entry<WitnessAccount> {
+        match result {
+            Ok(acc) => Entry::Found(WitnessAccount {
+                nonce: acc.nonce,
+                balance: acc.balance,
+                code: acc.code.clone(),
+            }),
+            Err(err) => Entry::Failed(format!("{}", err)),
+        }
+    }
+
+    pub(crate) fn into_state_account(self) -> StateAccount {
+        StateAccount {
+            nonce: self.nonce,
+            balance: self.balance,
+            code: self.code,
+        }
+    }
+}
+
+/// All the pre-state a transaction read from its provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Witness {
+    pub exist: BTreeMap<Address, bool>,
+    pub accounts: BTreeMap<Address, Entry<WitnessAccount>>,
+    pub storage: BTreeMap<Address, BTreeMap<H256, Entry<H256>>>,
+    pub storage_bytes: BTreeMap<Address, BTreeMap<H256, Entry<Vec<u8>>>>,
+    pub block_hashes: BTreeMap<u64, Entry<H256>>,
+}
+
+/// Provider decorator which records every read into a `Witness`.
+///
+/// Writes are passed through to the inner provider. Reads of entries which this
+/// execution already wrote are not recorded, so the witness only holds pre-state.
+pub struct WitnessRecorder<'a> {
+    provider: &'a mut dyn Provider,
+    witness: RefCell<Witness>,
+    written_accounts: BTreeSet<Address>,
+    written_storage: BTreeSet<(Address, H256)>,
+    written_storage_bytes: BTreeSet<(Address, H256)>,
+}
+
+impl<'a> WitnessRecorder<'a> {
+    pub fn new(provider: &'a mut dyn Provider) -> Self {
+        WitnessRecorder {
+            provider,
+            witness: RefCell::new(Witness::default()),
+            written_accounts: BTreeSet::new(),
+            written_storage: BTreeSet::new(),
+            written_storage_bytes: BTreeSet::new(),
+        }
+    }
+
+    pub fn into_witness(self) -> Witness {
+        self.witness.into_inner()
+    }
+}
+
+impl<'a> Provider for WitnessRecorder<'a> {
+    fn exist(&self, address: &Address) -> bool {
+        let exist = self.provider.exist(address);
+        if !self.written_accounts.contains(address) {
+            self.witness
+                .borrow_mut()
+                .exist
+                .entry(*address)
+                .or_insert(exist);
+        }
+        exist
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        let result = self.provider.account(address);
+        if !self.written_accounts.contains(address) {
+            self.witness
+                .borrow_mut()
+                .accounts
+                .entry(*address)
+                .or_insert(WitnessAccount::entry(&result));
+        }
+        result
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        self.written_accounts.insert(*address);
+        self.provider.update_account(address, bal, nonce)
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        self.written_accounts.insert(*address);
+        self.provider.create_contract(address, code)
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        let result = self.provider.storage_at(address, key);
+        if !self.written_storage.contains(&(*address, *key)) {
+            self.witness
+                .borrow_mut()
+                .storage
+                .entry(*address)
+                .or_default()
+                .entry(*key)
+                .or_insert(Entry::from_result(&result));
+        }
+        result
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        self.written_storage.insert((*address, *key));
+        self.provider.set_storage(address, key, value)
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        self.written_storage.insert((*address, *key));
+        self.provider.remove_storage(address, key)
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        let result = self.provider.storage_bytes_at(address, key);
+        if !self.written_storage_bytes.contains(&(*address, *key)) {
+            self.witness
+                .borrow_mut()
+                .storage_bytes
+                .entry(*address)
+                .or_default()
+                .entry(*key)
+                .or_insert(Entry::from_result(&result));
+        }
+        result
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        self.written_storage_bytes.insert((*address, *key));
+        self.provider.set_storage_bytes(address, key, value)
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        let result = self.provider.block_hash(block_no);
+        self.witness
+            .borrow_mut()
+            .block_hashes
+            .entry(block_no)
+            .or_insert(Entry::from_result(&result));
+        result
+    }
+}
+
+/// Provider which serves reads from a `Witness` only.
+///
+/// Any read the witness does not cover fails with `Error::MissingWitness`.
+/// `exist` can't fail, so its misses are only remembered; `finish` reports
+/// them once the execution is over.
+/// Writes are applied on top of the witness, so reads issued after a write see
+/// the new value just like they would against the original provider.
+pub struct WitnessProvider {
+    witness: Witness,
+    missing: RefCell<Vec<String>>,
+}
+
+impl WitnessProvider {
+    pub fn new(witness: Witness) -> Self {
+        WitnessProvider {
+            witness,
+            missing: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the witness with all writes applied, or an error if the
+    /// execution read anything the witness did not cover.
+    pub fn finish(self) -> Result<Witness, Error> {
+        let missing = self.missing.into_inner();
+        if missing.is_empty() {
+            Ok(self.witness)
+        } else {
+            Err(Error::MissingWitness {
+                what: missing.join(", "),
+            })
+        }
+    }
+
+    fn missing(&self, what: String) -> Error {
+        self.missing.borrow_mut().push(what.clone());
+        Error::MissingWitness { what }
+    }
+}
+
+impl Provider for WitnessProvider {
+    fn exist(&self, address: &Address) -> bool {
+        match self.witness.exist.get(address) {
+            Some(exist) => *exist,
+            None => {
+                self.missing(format!("existence of {:?}", address));
+                false
+            }
+        }
+    }
+
+    fn account(&self, address: &Address) -> Result<StateAccount, Error> {
+        match self.witness.accounts.get(address) {
+            Some(entry) => entry.to_result().map(WitnessAccount::into_state_account),
+            None => Err(self.missing(format!("account {:?}", address))),
+        }
+    }
+
+    fn update_account(&mut self, address: &Address, bal: &U256, nonce: &U256) -> Result<(), Error> {
+        match self.witness.accounts.get_mut(address) {
+            Some(Entry::Found(acc)) => {
+                acc.balance = *bal;
+                acc.nonce = *nonce;
+                Ok(())
+            }
+            _ => Err(self.missing(format!("account {:?}", address))),
+        }
+    }
+
+    fn create_contract(&mut self, address: &Address, code: &Vec<u8>) -> Result<(), Error> {
+        let acc = WitnessAccount {
+            nonce: U256::zero(),
+            balance: U256::zero(),
+            code: code.clone(),
+        };
+        self.witness.accounts.insert(*address, Entry::Found(acc));
+        self.witness.exist.insert(*address, true);
+        Ok(())
+    }
+
+    fn storage_at(&self, address: &Address, key: &H256) -> Result<H256, Error> {
+        match self.witness.storage.get(address).and_then(|s| s.get(key)) {
+            Some(entry) => entry.to_result(),
+            None => Err(self.missing(format!("storage {:?} of {:?}", key, address))),
+        }
+    }
+
+    fn set_storage(&mut self, address: &Address, key: &H256, value: &H256) -> Result<(), Error> {
+        self.witness
+            .storage
+            .entry(*address)
+            .or_default()
+            .insert(*key, Entry::Found(*value));
+        Ok(())
+    }
+
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        // A removed slot reads like one the provider never had.
+        let removed = Error::InvalidStorageKey { key: *key };
+        self.witness
+            .storage
+            .entry(*address)
+            .or_default()
+            .insert(*key, Entry::Failed(format!("{}", removed)));
+        Ok(())
+    }
+
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        match self.witness.storage_bytes.get(address).and_then(|s| s.get(key)) {
+            Some(entry) => entry.to_result(),
+            None => Err(self.missing(format!("storage bytes {:?} of {:?}", key, address))),
+        }
+    }
+
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        self.witness
+            .storage_bytes
+            .entry(*address)
+            .or_default()
+            .insert(*key, Entry::Found(value.to_vec()));
+        Ok(())
+    }
+
+    fn block_hash(&self, block_no: u64) -> Result<H256, Error> {
+        match self.witness.block_hashes.get(&block_no) {
+            Some(entry) => entry.to_result(),
+            None => Err(self.missing(format!("hash of block {}", block_no))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use execute::ExecutionOutcome;
+    use testing::{self, MemoryProvider};
+
+    /// Returns the value of storage slot zero.
+    const READ_SLOT: &str = r#"
+        (module
+            (import "env" "memory" (memory 1 16))
+            (import "env" "storage_read" (func $storage_read (param i32 i32)))
+            (import "env" "ret" (func $ret (param i32 i32)))
+            (func (export "call")
+                (call $storage_read (i32.const 0) (i32.const 32))
+                (call $ret (i32.const 32) (i32.const 32))))
+    "#;
+
+    fn setup() -> (MemoryProvider, Address) {
+        let contract = Address::repeat_byte(1);
+        let mut provider = MemoryProvider::default();
+        provider.deploy(contract, READ_SLOT);
+        provider
+            .set_storage(&contract, &H256::zero(), &H256::repeat_byte(7))
+            .unwrap();
+        (provider, contract)
+    }
+
+    fn record(provider: &mut MemoryProvider, contract: Address) -> (ExecutionOutcome, Witness) {
+        let mut recorder = WitnessRecorder::new(provider);
+        let outcome = testing::execute(&mut recorder, &testing::call(contract, vec![]));
+        (outcome, recorder.into_witness())
+    }
+
+    #[test]
+    fn replays_from_witness() {
+        let (mut provider, contract) = setup();
+        let (recorded, witness) = record(&mut provider, contract);
+        let data = recorded.clone().into_result().unwrap().data;
+        assert_eq!(data, H256::repeat_byte(7).as_bytes());
+
+        let mut replay = WitnessProvider::new(witness);
+        let replayed = testing::execute(&mut replay, &testing::call(contract, vec![]));
+        assert_eq!(replayed, recorded);
+        assert!(replay.finish().is_ok());
+    }
+
+    #[test]
+    fn read_missing_from_witness_aborts() {
+        let (mut provider, contract) = setup();
+        let (_, mut witness) = record(&mut provider, contract);
+        witness.storage.clear();
+
+        let mut replay = WitnessProvider::new(witness);
+        match testing::execute(&mut replay, &testing::call(contract, vec![])) {
+            ExecutionOutcome::HostFailure {
+                error: Error::MissingWitness { .. },
+                ..
+            } => {}
+            outcome => panic!("Replay kept running on a missing slot: {:?}", outcome),
+        }
+        assert!(replay.finish().is_err());
+    }
+}
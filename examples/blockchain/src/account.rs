@@ -9,6 +9,7 @@ pub struct Account {
     pub balance: U256,
     pub code: Vec<u8>,
     pub storage: HashMap<H256, H256>,
+    pub storage_bytes: HashMap<H256, Vec<u8>>,
 }
 
 impl Account {
@@ -19,6 +20,7 @@ impl Account {
             nonce: nonce,
             code: code,
             storage: HashMap::new(),
+            storage_bytes: HashMap::new(),
         }
     }
 }
@@ -1,13 +1,14 @@
 use crate::account::Account;
 use crate::block::Block;
 use durian::address::Address;
+use durian::block::BlockEnv;
+use durian::dump::{DumpAccount, StateDump};
 use durian::error::Error;
 use durian::execute::ResultData;
 use durian::provider::{Provider, StateAccount};
-use durian::transaction::{Action, Transaction};
+use durian::transaction::Transaction;
 use hex_literal::hex;
 use primitive_types::{H160, H256, U256};
-use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 use std::time::SystemTime;
 
@@ -17,42 +18,11 @@ pub struct Blockchain {
     accounts: HashMap<String, Account>,
     counter: i32,
     transactions: HashMap<H256, (Transaction, ResultData)>,
-}
-
-pub fn transaction_hash(transaction: &Transaction) -> H256 {
-    let mut bytes = Vec::new();
-    let mut tmp = Vec::new();
-    tmp.resize(32, 0);
-
-    bytes.extend_from_slice(transaction.sender.as_bytes());
-
-    transaction.value.to_little_endian(&mut tmp);
-    bytes.extend_from_slice(tmp.as_slice());
-
-    transaction.gas.to_little_endian(&mut tmp);
-    bytes.extend_from_slice(tmp.as_slice());
-
-    transaction.gas_price.to_little_endian(&mut tmp);
-    bytes.extend_from_slice(tmp.as_slice());
-
-    match &transaction.action {
-        Action::Create(code, salt) => {
-            bytes.extend_from_slice(code.as_slice());
-            bytes.extend_from_slice(salt.as_bytes());
-        }
-        Action::Call(address) => {
-            bytes.extend_from_slice(address.as_bytes());
-        }
-    }
-
-    bytes.extend_from_slice(transaction.args.as_slice());
-
-    H256::from_slice(Keccak256::digest(&bytes).as_slice())
+    chain_id: U256,
 }
 
 impl Blockchain {
     pub fn new() -> Blockchain {
-        let gen = Block::new(0, H256::zero());
         let mut accounts = HashMap::new();
         let addr = Address::from_slice(&hex!("004ec07d2329997267ec62b4166639513386f32e")[..]);
 
@@ -77,21 +47,30 @@ impl Blockchain {
             Account::new(addr, U256::from(1000000), U256::zero(), vec![]),
         );
 
+        let state_root = Blockchain::dump_accounts(&accounts).state_root();
+        let gen = Block::new(0, H256::zero(), state_root);
+
         Blockchain {
             blocks: vec![gen],
             accounts: accounts,
             counter: 0,
             transactions: HashMap::new(),
+            chain_id: U256::from(1),
         }
     }
 
     pub fn commit(&mut self) {
-        let block = Block::new(self.blocks.len() as u64, self.blocks.last().unwrap().hash());
+        let block = Block::new(
+            self.blocks.len() as u64,
+            self.blocks.last().unwrap().hash(),
+            self.dump().state_root(),
+        );
 
         info!(
-            "Committing new block. num: {}, hash: {}",
+            "Committing new block. num: {}, hash: {}, state root: {}",
             block.num,
-            block.hash()
+            block.hash(),
+            block.state_root
         );
         self.blocks.push(block);
 
@@ -111,6 +90,27 @@ impl Blockchain {
         }
     }
 
+    pub fn dump(&self) -> StateDump {
+        Blockchain::dump_accounts(&self.accounts)
+    }
+
+    fn dump_accounts(accounts: &HashMap<String, Account>) -> StateDump {
+        let mut dump = StateDump::default();
+        for acc in accounts.values() {
+            dump.accounts.insert(
+                acc.address,
+                DumpAccount {
+                    nonce: acc.nonce,
+                    balance: acc.balance,
+                    code: acc.code.clone(),
+                    storage: acc.storage.iter().map(|(k, v)| (*k, *v)).collect(),
+                    storage_bytes: acc.storage_bytes.clone().into_iter().collect(),
+                },
+            );
+        }
+        dump
+    }
+
     pub fn address_from_alias(&self, alias: &str) -> Address {
         self.accounts.get(alias).unwrap().address
     }
@@ -155,7 +155,13 @@ impl Blockchain {
     }
 
     pub fn add_transactions(&mut self, transaction: Transaction, result: ResultData) -> H256 {
-        let txhash = transaction_hash(&transaction);
+        // The example chain has no signed transactions, the current nonce of the
+        // sender tells apart the transactions it sends.
+        let nonce = self
+            .account(&transaction.sender)
+            .map(|acc| acc.nonce)
+            .unwrap_or_default();
+        let txhash = transaction.hash(&nonce, &self.chain_id);
         self.transactions.insert(txhash, (transaction, result));
         return txhash;
     }
@@ -172,6 +178,21 @@ impl Blockchain {
     pub fn latest_block_hash(&self) -> H256 {
         self.blocks.last().unwrap().hash()
     }
+
+    /// Environment of the latest block, the one transactions are executed in.
+    pub fn block_env(&self) -> BlockEnv {
+        let block = self.blocks.last().unwrap();
+        let duration_since_epoch = block.time.duration_since(SystemTime::UNIX_EPOCH);
+        BlockEnv {
+            number: block.num,
+            timestamp: duration_since_epoch.unwrap().as_secs(),
+            author: self.address_from_alias("alice"),
+            difficulty: U256::zero(),
+            gas_limit: U256::from(1000000),
+            base_fee: U256::zero(),
+            chain_id: self.chain_id,
+        }
+    }
 }
 
 impl Provider for Blockchain {
@@ -207,34 +228,28 @@ impl Provider for Blockchain {
         Ok(())
     }
 
-    fn block_hash(&self, num: u64) -> Result<H256, Error> {
-        Ok(self.blocks.get(num as usize).unwrap().hash())
-    }
-
-    fn timestamp(&self) -> u64 {
-        let duration_since_epoch = self
-            .blocks
-            .last()
-            .unwrap()
-            .time
-            .duration_since(SystemTime::UNIX_EPOCH);
-        duration_since_epoch.unwrap().as_secs()
-    }
-
-    fn block_number(&self) -> u64 {
-        self.blocks.last().unwrap().num
+    fn remove_storage(&mut self, address: &Address, key: &H256) -> Result<(), Error> {
+        let acc = self.account_mut(address)?;
+        acc.storage.remove(key);
+        Ok(())
     }
 
-    fn block_author(&self) -> Result<Address, Error> {
-        Ok(self.address_from_alias("alice"))
+    fn storage_bytes_at(&self, address: &Address, key: &H256) -> Result<Vec<u8>, Error> {
+        let acc = self.account(address)?;
+        match acc.storage_bytes.get(key) {
+            Some(value) => Ok(value.clone()),
+            _ => Err(Error::InvalidStorageKey { key: *key }),
+        }
     }
 
-    fn difficulty(&self) -> Result<U256, Error> {
-        Err(Error::NotSupported)
+    fn set_storage_bytes(&mut self, address: &Address, key: &H256, value: &[u8]) -> Result<(), Error> {
+        let acc = self.account_mut(address)?;
+        acc.storage_bytes.insert(*key, value.to_vec());
+        Ok(())
     }
 
-    fn gas_limit(&self) -> Result<U256, Error> {
-        Ok(U256::from(1000000))
+    fn block_hash(&self, num: u64) -> Result<H256, Error> {
+        Ok(self.blocks.get(num as usize).unwrap().hash())
     }
 
     fn exist(&self, address: &Address) -> bool {
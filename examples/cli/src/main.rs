@@ -7,16 +7,50 @@ extern crate simple_logger;
 extern crate log;
 
 use blockchain::blockchain::Blockchain;
+use durian::abi::{Abi, Token};
 use durian::execute;
+use durian::execute::ExecutionOutcome;
+use durian::record::{Recording, Replayer};
 use durian::transaction::Transaction;
 use primitive_types::{H256, U256};
 use log::Level;
-use std::fs::File;
-use std::io::Read;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+
+fn replay(file_path: &str) {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(err) => panic!(err.to_string()),
+    };
+    let recording = Recording::read_from(BufReader::new(file)).unwrap();
+    info!("Replaying {} provider calls", recording.interactions.len());
+
+    let mut replayer = Replayer::new(&recording);
+    let ret = execute::execute(&mut replayer, &recording.env, &recording.transaction);
+    info!("ret: {:?}", ret);
+    if let Ok(ExecutionOutcome::ContractPanic {
+        payload: Some(payload),
+        ..
+    }) = &ret
+    {
+        error!("Contract panicked: {}", payload);
+    }
+
+    match replayer.finish() {
+        Ok(()) => info!("Replay matched the recording"),
+        Err(err) => error!("{}", err),
+    }
+}
 
 fn main() {
     simple_logger::init_with_level(Level::Debug).unwrap();
 
+    let args: Vec<String> = ::std::env::args().collect();
+    if args.len() == 3 && args[1] == "replay" {
+        replay(&args[2]);
+        return;
+    }
+
     let mut bc = Blockchain::new();
 
     let file_path = "./examples/cli/compiled-contracts/token.wasm";
@@ -29,14 +63,21 @@ fn main() {
         panic!(err.to_string());
     }
 
+    let abi_path = "./examples/cli/compiled-contracts/token.json";
+    let abi = match fs::read_to_string(abi_path) {
+        Ok(json) => Abi::from_json(&json).unwrap(),
+        Err(err) => panic!(err.to_string()),
+    };
+
     bc.commit();
 
     // deploy token contract
-    let params1 = vec![
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF,
-    ];
+    let params1 = abi
+        .constructor
+        .as_ref()
+        .unwrap()
+        .encode_input(&[Token::Uint(U256::max_value())])
+        .unwrap();
     let tx1 = Transaction::make_create(
         bc.address_from_alias("alice"),
         U256::zero(),
@@ -47,7 +88,8 @@ fn main() {
         H256::zero(),
     );
 
-    let ret1 = execute::execute(&mut bc, &tx1).unwrap();
+    let env = bc.block_env();
+    let ret1 = execute::execute(&mut bc, &env, &tx1).unwrap().into_result().unwrap();
 
     //info!("ret1: {:?}", ret1);
     bc.inc_nonce("alice");
@@ -55,14 +97,14 @@ fn main() {
     let contract = ret1.contract;
     bc.add_transactions(tx1, ret1);
 
-    // transfer to bob: 0xa9059cbb
-    let mut params2 = vec![0xa9, 0x05, 0x9c, 0xbb, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-    params2.append(&mut bc.address_from_alias("bob").as_bytes_mut().to_vec());
-    params2.append(&mut vec![
-        0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-        0xFF, 0xFF,
-    ]);
+    // transfer to bob
+    let transfer = abi.function("transfer").unwrap();
+    let params2 = transfer
+        .encode_input(&[
+            Token::Address(bc.address_from_alias("bob")),
+            Token::Uint(U256::max_value() >> 24),
+        ])
+        .unwrap();
 
     let tx2 = Transaction::make_call(
         bc.address_from_alias("alice"),
@@ -73,15 +115,24 @@ fn main() {
         params2,
     );
 
-    let ret2 = execute::execute(&mut bc, &tx2).unwrap();
+    let env = bc.block_env();
+    let ret2 = execute::execute(&mut bc, &env, &tx2).unwrap().into_result().unwrap();
     info!("ret2: {:?}", ret2);
+    info!("transfer: {:?}", transfer.decode_output(&ret2.data));
+    for log in &ret2.logs {
+        match abi.decode_log(log) {
+            Ok(event) => info!("event: {}", event),
+            Err(err) => warn!("undecodable log {:?}: {}", log, err),
+        }
+    }
     bc.inc_nonce("alice");
     bc.commit();
     bc.add_transactions(tx2, ret2);
 
 
-    // total_supply: 0x18160ddd
-    let params3 = vec![0x18, 0x16, 0x0d, 0xdd];
+    // total supply
+    let total_supply = abi.function("totalSupply").unwrap();
+    let params3 = total_supply.encode_input(&[]).unwrap();
     let tx3 = Transaction::make_call(
         bc.address_from_alias("alice"),
         contract,
@@ -90,16 +141,20 @@ fn main() {
         U256::zero(),
         params3,
     );
-    let ret3 = execute::execute(&mut bc, &tx3).unwrap();
+    let env = bc.block_env();
+    let ret3 = execute::execute(&mut bc, &env, &tx3).unwrap().into_result().unwrap();
     info!("ret3: {:?}", ret3);
+    info!("totalSupply: {:?}", total_supply.decode_output(&ret3.data));
     bc.inc_nonce("alice");
     bc.commit();
     bc.add_transactions(tx3, ret3);
 
 
-    // balance_of: 0x70a08231
-    let mut params4 = vec![0x70, 0xa0, 0x82, 0x31, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-    params4.append(&mut bc.address_from_alias("bob").as_bytes_mut().to_vec());
+    // balance of bob
+    let balance_of = abi.function("balanceOf").unwrap();
+    let params4 = balance_of
+        .encode_input(&[Token::Address(bc.address_from_alias("bob"))])
+        .unwrap();
 
     let tx4 = Transaction::make_call(
         bc.address_from_alias("bob"),
@@ -109,8 +164,10 @@ fn main() {
         U256::zero(),
         params4,
     );
-    let ret4 = execute::execute(&mut bc, &tx4).unwrap();
+    let env = bc.block_env();
+    let ret4 = execute::execute(&mut bc, &env, &tx4).unwrap().into_result().unwrap();
     info!("ret4: {:?}", ret4);
+    info!("balanceOf: {:?}", balance_of.decode_output(&ret4.data));
     bc.inc_nonce("bob");
     bc.commit();
     bc.add_transactions(tx4, ret4);
@@ -17,7 +17,11 @@ mod provider_impl;
 
 use blockchain::blockchain::Blockchain;
 use capnp_rpc::{rpc_twoparty_capnp, twoparty, RpcSystem};
+use durian::abi::{Abi, Token};
 use durian::address::Address;
+use durian::block::BlockEnv;
+use durian::log_entry::LogEntry;
+use durian::panic_payload;
 use durian::transaction::{Action, Transaction};
 use durian_capnp::executor;
 use futures::task::LocalSpawn;
@@ -26,7 +30,7 @@ use futures::FutureExt;
 use log::Level;
 use primitive_types::{H256, U256};
 use provider_impl::ProviderImpl;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
 use std::net::ToSocketAddrs;
 use std::sync::Arc;
@@ -88,12 +92,15 @@ fn main() {
             panic!(err.to_string());
         }
 
+        let abi_path = "./examples/cli/compiled-contracts/token.json";
+        let abi = Abi::from_json(&fs::read_to_string(abi_path)?)?;
+
         info!("=== deploy token contract");
-        let params1: Vec<u8> = vec![
-            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-            0xFF, 0xFF, 0xFF, 0xFF,
-        ];
+        let params1 = abi
+            .constructor
+            .as_ref()
+            .unwrap()
+            .encode_input(&[Token::Uint(U256::max_value())])?;
 
         let tx1 = Transaction::make_create(
             BC.lock()?.address_from_alias("alice"),
@@ -107,6 +114,8 @@ fn main() {
 
         let mut request = executor.execute_request();
         {
+            let mut builder = request.get().init_block_env();
+            build_block_env(&mut builder, &BC.lock()?.block_env());
             let mut builder = request.get().init_transaction();
             build_tx(&mut builder, &tx1);
             request.get().set_provider(provider.clone());
@@ -121,15 +130,12 @@ fn main() {
         let contract = ret1.contract;
         BC.lock()?.add_transactions(tx1, ret1);
 
-        // transfer to bob: 0xa9059cbb
+        // transfer to bob
         info!("=== transfer to bob");
-        let mut params2 = vec![0xa9, 0x05, 0x9c, 0xbb, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-        params2.append(&mut BC.lock()?.address_from_alias("bob").as_bytes_mut().to_vec());
-        params2.append(&mut vec![
-            0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-            0xFF, 0xFF, 0xFF, 0xFF,
-        ]);
+        let params2 = abi.function("transfer").unwrap().encode_input(&[
+            Token::Address(BC.lock()?.address_from_alias("bob")),
+            Token::Uint(U256::max_value() >> 24),
+        ])?;
 
         let tx2 = Transaction::make_call(
             BC.lock()?.address_from_alias("alice"),
@@ -142,6 +148,8 @@ fn main() {
 
         let mut request = executor.execute_request();
         {
+            let mut builder = request.get().init_block_env();
+            build_block_env(&mut builder, &BC.lock()?.block_env());
             let mut builder = request.get().init_transaction();
             build_tx(&mut builder, &tx2);
             request.get().set_provider(provider.clone());
@@ -153,9 +161,9 @@ fn main() {
         BC.lock()?.commit();
         BC.lock()?.add_transactions(tx2, ret2);
 
-        // total_supply: 0x18160ddd
+        // total supply
         info!("=== total_supply");
-        let params3 = vec![0x18, 0x16, 0x0d, 0xdd];
+        let params3 = abi.function("totalSupply").unwrap().encode_input(&[])?;
         let tx3 = Transaction::make_call(
             BC.lock()?.address_from_alias("alice"),
             contract,
@@ -166,6 +174,8 @@ fn main() {
         );
         let mut request = executor.execute_request();
         {
+            let mut builder = request.get().init_block_env();
+            build_block_env(&mut builder, &BC.lock()?.block_env());
             let mut builder = request.get().init_transaction();
             build_tx(&mut builder, &tx3);
             request.get().set_provider(provider.clone());
@@ -177,10 +187,12 @@ fn main() {
         BC.lock()?.commit();
         BC.lock()?.add_transactions(tx3, ret3);
 
-        // balance_of: 0x70a08231
+        // balance of bob
         info!("=== balance_of bob");
-        let mut params4 = vec![0x70, 0xa0, 0x82, 0x31, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-        params4.append(&mut BC.lock()?.address_from_alias("bob").as_bytes_mut().to_vec());
+        let params4 = abi
+            .function("balanceOf")
+            .unwrap()
+            .encode_input(&[Token::Address(BC.lock()?.address_from_alias("bob"))])?;
 
         let tx4 = Transaction::make_call(
             BC.lock()?.address_from_alias("bob"),
@@ -192,6 +204,8 @@ fn main() {
         );
         let mut request = executor.execute_request();
         {
+            let mut builder = request.get().init_block_env();
+            build_block_env(&mut builder, &BC.lock()?.block_env());
             let mut builder = request.get().init_transaction();
             build_tx(&mut builder, &tx4);
             request.get().set_provider(provider.clone());
@@ -209,22 +223,86 @@ fn main() {
 
 impl<'a> From<durian_capnp::executor::execute_results::Reader<'a>> for durian::execute::ResultData {
     fn from(reader: durian_capnp::executor::execute_results::Reader<'a>) -> Self {
+        let status = reader.get_result_data().unwrap().get_status().unwrap();
+        if status == durian_capnp::Status::ContractPanic {
+            let raw_payload = reader.get_result_data().unwrap().get_panic_payload().unwrap();
+            if !raw_payload.is_empty() {
+                warn!("Contract panicked: {}", panic_payload::decode(raw_payload));
+            }
+        }
+        if status != durian_capnp::Status::Success {
+            warn!(
+                "Execution failed ({:?}): {}",
+                status,
+                reader.get_result_data().unwrap().get_message().unwrap()
+            );
+        }
+        let debug_messages = reader
+            .get_result_data()
+            .unwrap()
+            .get_debug_messages()
+            .unwrap()
+            .iter()
+            .map(|entry| durian::execute::DebugMessage {
+                gas_used: U256::from_little_endian(entry.get_gas_used().unwrap()),
+                message: entry.get_message().unwrap().to_string(),
+            })
+            .collect();
         let gas_left =
             U256::from_little_endian(reader.get_result_data().unwrap().get_gas_left().unwrap());
         let data = reader.get_result_data().unwrap().get_data().unwrap();
         let contract =
             Address::from_slice(reader.get_result_data().unwrap().get_contract().unwrap());
-        let logs = vec![];
+        let logs = reader
+            .get_result_data()
+            .unwrap()
+            .get_logs()
+            .unwrap()
+            .iter()
+            .map(|entry| LogEntry {
+                address: Address::from_slice(entry.get_address().unwrap()),
+                topics: entry
+                    .get_topics()
+                    .unwrap()
+                    .iter()
+                    .map(|topic| H256::from_slice(topic.unwrap()))
+                    .collect(),
+                data: entry.get_data().unwrap().iter().map(|byte| byte as u8).collect(),
+            })
+            .collect();
 
         durian::execute::ResultData {
             gas_left: gas_left,
             data: data.to_vec(),
             contract: contract,
             logs: logs,
+            accesses: Default::default(),
+            debug_messages: debug_messages,
         }
     }
 }
 
+fn build_block_env(builder: &mut durian_capnp::block_env::Builder, env: &BlockEnv) {
+    let mut tmp = Vec::new();
+    tmp.resize(32, 0);
+
+    builder.set_number(env.number);
+    builder.set_timestamp(env.timestamp);
+    builder.set_author(env.author.as_bytes());
+
+    env.difficulty.to_little_endian(&mut tmp);
+    builder.set_difficulty(&tmp);
+
+    env.gas_limit.to_little_endian(&mut tmp);
+    builder.set_gas_limit(&tmp);
+
+    env.base_fee.to_little_endian(&mut tmp);
+    builder.set_base_fee(&tmp);
+
+    env.chain_id.to_little_endian(&mut tmp);
+    builder.set_chain_id(&tmp);
+}
+
 fn build_tx(builder: &mut durian_capnp::transaction::Builder, tx: &Transaction) {
     let mut tmp = Vec::new();
     tmp.resize(32, 0);
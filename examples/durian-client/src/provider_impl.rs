@@ -121,4 +121,66 @@ impl<'a> provider::Server for ProviderImpl<'a> {
             }
         }
     }
+
+    fn remove_storage(
+        &mut self,
+        params: provider::RemoveStorageParams,
+        _: provider::RemoveStorageResults,
+    ) -> ::capnp::capability::Promise<(), ::capnp::Error> {
+        debug!("server called `remove_storage` method");
+
+        let address = Address::from_slice(pry!(pry!(params.get()).get_address()));
+        let key = H256::from_slice(pry!(pry!(params.get()).get_key()));
+
+        match self.bc.lock().unwrap().remove_storage(&address, &key) {
+            Ok(()) => {
+                return Promise::ok(());
+            }
+            Err(e) => {
+                return Promise::err(::capnp::Error::failed(format!("{}", e)));
+            }
+        }
+    }
+
+    fn storage_bytes_at(
+        &mut self,
+        params: provider::StorageBytesAtParams,
+        mut results: provider::StorageBytesAtResults,
+    ) -> ::capnp::capability::Promise<(), ::capnp::Error> {
+        debug!("server called `storage_bytes_at` method");
+
+        let address = Address::from_slice(pry!(pry!(params.get()).get_address()));
+        let key = H256::from_slice(pry!(pry!(params.get()).get_key()));
+
+        match self.bc.lock().unwrap().storage_bytes_at(&address, &key) {
+            Ok(value) => {
+                results.get().set_value(&value);
+                return Promise::ok(());
+            }
+            Err(e) => {
+                return Promise::err(::capnp::Error::failed(format!("{}", e)));
+            }
+        }
+    }
+
+    fn set_storage_bytes(
+        &mut self,
+        params: provider::SetStorageBytesParams,
+        _: provider::SetStorageBytesResults,
+    ) -> ::capnp::capability::Promise<(), ::capnp::Error> {
+        debug!("server called `set_storage_bytes` method");
+
+        let address = Address::from_slice(pry!(pry!(params.get()).get_address()));
+        let key = H256::from_slice(pry!(pry!(params.get()).get_key()));
+        let value = pry!(pry!(params.get()).get_value()).to_vec();
+
+        match self.bc.lock().unwrap().set_storage_bytes(&address, &key, &value) {
+            Ok(()) => {
+                return Promise::ok(());
+            }
+            Err(e) => {
+                return Promise::err(::capnp::Error::failed(format!("{}", e)));
+            }
+        }
+    }
 }
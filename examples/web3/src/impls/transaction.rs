@@ -73,7 +73,11 @@ impl TransactionRPC for TransactionRPCImpl {
 			params_vec,
 		);
 
-		let ret3 =execute::execute(&mut *bc, &tx_call.clone()).unwrap();
+		let env = bc.block_env();
+		let ret3 = execute::execute(&mut *bc, &env, &tx_call.clone())
+			.unwrap()
+			.into_result()
+			.unwrap();
 		println!("the value inside ret3 {:?}", ret3);
 		let res = Bytes::new(ret3.data);
 		Ok(res)